@@ -6,14 +6,18 @@ use std::sync::{Arc, RwLock};
 use std::panic::{self, AssertUnwindSafe};
 
 use jni::objects::{JClass, JString};
-use jni::sys::{jboolean, jint, jstring};
+use jni::sys::{jboolean, jdouble, jint, jstring};
 use jni::JNIEnv;
 
-use ore_lib::args::MineArgs;
+use ore_lib::args::{ClaimArgs, MineArgs};
+use ore_lib::boost_select::parse_boosts;
+use ore_lib::confirm::AutoYes;
+use ore_lib::control::CancelMode;
 use ore_lib::miner::Miner;
+use ore_lib::profile::Profile;
 use ore_lib::Manager;
 
-use crate::utils::{string_unwrap, throw_java_exception};
+use crate::utils::{panic_message, string_unwrap, throw_java_exception};
 
 // #[no_mangle]
 // pub extern "system" fn JNI_OnLoad(vm: JavaVM, _reserved: *mut c_void) -> jint {
@@ -114,16 +118,119 @@ pub extern "system" fn Java_industries_dlp8_rust_OreJNILib_startMining(
         pool_url: Some(pool_url),
         cores,
         buffer_time,
-        boost_1: None,
-        boost_2: None,
-        boost_3: None,
+        ..MineArgs::default()
     };
 
-    let manager = Manager::new(miner, mining_args);
+    start_manager(&mut env, Manager::new(miner, mining_args))
+}
+
+// Like startMining, plus the throttle limits and a comma separated list of boost mints
+#[no_mangle]
+pub extern "system" fn Java_industries_dlp8_rust_OreJNILib_startMiningWithOptions(
+    mut env: JNIEnv,
+    _class: JClass,
+    keypair_filepath: JString,
+    rpc_client: JString,
+    priority_fee: jint,
+    dynamic_fee_url: JString,
+    dynamic_fee: jboolean,
+    fee_payer_filepath: JString,
+    jito_client: JString,
+    tip: jint,
+    pool_url: JString,
+    cores: jint,
+    buffer_time: jint,
+    max_duty_cycle: jint,
+    batch_sleep_ms: jint,
+    max_temp: jint,
+    boosts: JString,
+) -> jint {
+    let keypair_filepath = string_unwrap(&mut env, keypair_filepath);
+    let rpc_client = string_unwrap(&mut env, rpc_client);
+    let priority_fee = priority_fee as u64;
+    let dynamic_fee_url = string_unwrap(&mut env, dynamic_fee_url);
+    let dynamic_fee = dynamic_fee != 0;
+    let fee_payer_filepath = string_unwrap(&mut env, fee_payer_filepath);
+    let jito_client = string_unwrap(&mut env, jito_client);
+    let tip = tip as u64;
+    let pool_url = string_unwrap(&mut env, pool_url);
+    let cores = cores as u64;
+    let buffer_time = buffer_time as u64;
+    // Zero or less disables a throttle limit
+    let max_duty_cycle = Some(max_duty_cycle)
+        .filter(|duty| (1..100).contains(duty))
+        .map(|duty| duty as u8);
+    let batch_sleep_ms = batch_sleep_ms.max(0) as u64;
+    let max_temp = Some(max_temp).filter(|temp| temp.gt(&0)).map(|temp| temp as f64);
+    // A comma separated list of boost mints, empty for none
+    let boosts = string_unwrap(&mut env, boosts);
+    let boosts = match parse_boosts(boosts.split(',')) {
+        Ok(boosts) => boosts,
+        Err(e) => {
+            let error_msg = format!("Error parsing boosts: {}", e);
+            throw_java_exception(&mut env, "java/lang/IllegalArgumentException", &error_msg);
+            return -1;
+        }
+    };
+
+    let rpc_client = Arc::new(RpcClient::new(rpc_client));
+    let jito_client = Arc::new(RpcClient::new(jito_client));
+    let tip = Arc::new(RwLock::new(tip));
+
+    let miner = Miner::new(
+        rpc_client,
+        Some(priority_fee),
+        Some(keypair_filepath),
+        Some(dynamic_fee_url),
+        dynamic_fee,
+        Some(fee_payer_filepath),
+        jito_client,
+        tip,
+    );
+
+    let mining_args = MineArgs {
+        pool_url: Some(pool_url),
+        cores,
+        buffer_time,
+        max_duty_cycle,
+        batch_sleep_ms,
+        max_temp,
+        boosts,
+        ..MineArgs::default()
+    };
+
+    start_manager(&mut env, Manager::new(miner, mining_args))
+}
+
+#[no_mangle]
+pub extern "system" fn Java_industries_dlp8_rust_OreJNILib_startMiningFromProfile(
+    mut env: JNIEnv,
+    _class: JClass,
+    profile_file: JString,
+    profile: JString,
+) -> jint {
+    let profile_file = string_unwrap(&mut env, profile_file);
+    let profile = string_unwrap(&mut env, profile);
+
+    // An empty path selects the default profiles file
+    let profile_file = Some(profile_file).filter(|path| !path.is_empty());
+    let profile = match Profile::load(profile_file.as_deref(), &profile) {
+        Ok(profile) => profile,
+        Err(e) => {
+            let error_msg = format!("Error loading profile: {:?}", e);
+            throw_java_exception(&mut env, "java/lang/RuntimeException", &error_msg);
+            return -1;
+        }
+    };
+
+    start_manager(&mut env, Manager::from_profile(&profile))
+}
+
+fn start_manager(env: &mut JNIEnv, manager: Manager) -> jint {
     Manager::set_global_manager(manager);
     let global_manager = Manager::get_global_manager();
 
-    let result = tokio::runtime::Runtime::new().unwrap().block_on(async {
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
         let mut manager = global_manager.lock().await;
         match manager.start_mining() {
             Ok(_) => {
@@ -132,26 +239,118 @@ pub extern "system" fn Java_industries_dlp8_rust_OreJNILib_startMining(
             }
             Err(e) => {
                 let error_msg = format!("Error starting mining: {:?}", e);
-                throw_java_exception(&mut env, "java/lang/RuntimeException", &error_msg);
+                throw_java_exception(env, "java/lang/RuntimeException", &error_msg);
                 -1
             }
         }
-    });
-
-    result
+    })
 }
 
 #[no_mangle]
 pub extern "system" fn Java_industries_dlp8_rust_OreJNILib_stopMining(
+    env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    stop_manager(env, None)
+}
+
+/// Stops mining with an explicit cancel mode:
+/// 0 = immediate, 1 = after submit, 2 = submit best now.
+#[no_mangle]
+pub extern "system" fn Java_industries_dlp8_rust_OreJNILib_stopMiningWithMode(
+    env: JNIEnv,
+    _class: JClass,
+    mode: jint,
+) -> jint {
+    stop_manager(env, Some(CancelMode::from(mode as u8)))
+}
+
+/// Changes the number of mining threads of the running miner. If `immediate` is false,
+/// the new count takes effect at the next round.
+#[no_mangle]
+pub extern "system" fn Java_industries_dlp8_rust_OreJNILib_setCores(
     mut env: JNIEnv,
     _class: JClass,
+    cores: jint,
+    immediate: jboolean,
 ) -> jint {
+    let global_manager = Manager::get_global_manager();
+    let result = tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let mut manager = global_manager.lock().await;
+        manager.set_cores(cores.max(0) as u64, immediate != 0)
+    });
+
+    match result {
+        Ok(_) => 0,
+        Err(e) => {
+            let error_msg = format!("Error setting cores: {:?}", e);
+            throw_java_exception(&mut env, "java/lang/RuntimeException", &error_msg);
+            -1
+        }
+    }
+}
+
+/// Claims mining rewards with the running miner's keypair, without prompting: the app
+/// confirms with the user before calling this. A zero `amount` claims everything, an
+/// empty `to` claims to the signer's token account, an empty `pool_url` claims from
+/// the proof and an empty `memo` attaches none. Returns the outcome as JSON.
+#[no_mangle]
+pub extern "system" fn Java_industries_dlp8_rust_OreJNILib_claim(
+    mut env: JNIEnv,
+    _class: JClass,
+    amount: jdouble,
+    to: JString,
+    pool_url: JString,
+    memo: JString,
+) -> jstring {
+    let to = string_unwrap(&mut env, to);
+    let pool_url = string_unwrap(&mut env, pool_url);
+    let memo = string_unwrap(&mut env, memo);
+    let args = ClaimArgs {
+        amount: Some(amount).filter(|amount| amount.gt(&0.0)),
+        to: Some(to).filter(|to| !to.is_empty()),
+        pool_url: Some(pool_url).filter(|url| !url.is_empty()),
+        memo: Some(memo).filter(|memo| !memo.is_empty()),
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let global_manager = Manager::get_global_manager();
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let miner = global_manager
+                .lock()
+                .await
+                .miner
+                .clone()
+                .with_confirmer(Arc::new(AutoYes));
+            miner.claim_rewards(args).await
+        })
+    }));
+
+    let error_msg = match result {
+        Ok(Ok(claim)) => {
+            let json = serde_json::to_string(&claim).unwrap();
+            return env
+                .new_string(json)
+                .expect("Couldn't create Java string!")
+                .into_raw();
+        }
+        Ok(Err(e)) => format!("Error claiming: {:?}", e),
+        Err(panic_error) => format!("Error claiming: {}", panic_message(panic_error)),
+    };
+    throw_java_exception(&mut env, "java/lang/RuntimeException", &error_msg);
+    std::ptr::null_mut()
+}
+
+fn stop_manager(mut env: JNIEnv, mode: Option<CancelMode>) -> jint {
     let result = panic::catch_unwind(AssertUnwindSafe(|| {
         let global_manager = Manager::get_global_manager();
 
         tokio::runtime::Runtime::new().unwrap().block_on(async {
             let manager = global_manager.lock().await;
-            manager.stop_mining()
+            match mode {
+                Some(mode) => manager.stop_mining_with(mode),
+                None => manager.stop_mining(),
+            }
         })
     }));
 
@@ -167,13 +366,7 @@ pub extern "system" fn Java_industries_dlp8_rust_OreJNILib_stopMining(
             -1
         }
         Err(panic_error) => {
-            let error_msg = if let Some(s) = panic_error.downcast_ref::<String>() {
-                s.clone()
-            } else if let Some(s) = panic_error.downcast_ref::<&str>() {
-                s.to_string()
-            } else {
-                "Unknown panic occurred".to_string()
-            };
+            let error_msg = panic_message(panic_error);
             eprintln!("Panic occurred: {}", error_msg);
             throw_java_exception(&mut env, "java/lang/RuntimeException", &error_msg);
             -1
@@ -1,3 +1,5 @@
+use std::any::Any;
+
 use jni::JNIEnv;
 use jni::objects::JString;
 
@@ -11,3 +13,13 @@ pub fn throw_java_exception(env: &mut JNIEnv, exception_class: &str, message: &s
     let exception_class = env.find_class(exception_class).unwrap();
     env.throw_new(exception_class, message).unwrap();
 }
+
+pub fn panic_message(panic_error: Box<dyn Any + Send>) -> String {
+    if let Some(s) = panic_error.downcast_ref::<String>() {
+        s.clone()
+    } else if let Some(s) = panic_error.downcast_ref::<&str>() {
+        s.to_string()
+    } else {
+        "Unknown panic occurred".to_string()
+    }
+}
@@ -1,4 +1,10 @@
 use clap::{arg, command, Parser, Subcommand};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    boost_select::BoostMode, bus::BusStrategy, control::CancelMode, core_alloc::PinMode,
+    profitability::UnprofitablePolicy,
+};
 
 #[derive(Parser, Debug)]
 pub struct BalanceArgs {
@@ -27,6 +33,54 @@ pub struct BenchmarkArgs {
         default_value = "1"
     )]
     pub cores: u64,
+
+    #[arg(
+        long,
+        value_name = "CORE_IDS",
+        value_delimiter = ',',
+        help = "Pin threads to these core ids, e.g. 2,4,6. Threads are assigned round-robin."
+    )]
+    pub core_list: Vec<usize>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        help = "How to pin threads to cores when no core list is given.",
+        default_value = "physical"
+    )]
+    pub pin: PinMode,
+
+    #[arg(
+        long,
+        short,
+        value_name = "SECONDS",
+        help = "How long to run each benchmark for.",
+        default_value = "30"
+    )]
+    pub duration: u64,
+
+    #[arg(
+        long,
+        value_name = "CHALLENGE",
+        help = "The challenge to hash, in base58, or \"random\". Defaults to all zeros."
+    )]
+    pub challenge: Option<String>,
+
+    #[arg(
+        long,
+        help = "Benchmark every core count from 1 up to --cores and report the scaling."
+    )]
+    pub sweep: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct BoostsArgs {
+    #[arg(
+        value_name = "ADDRESS",
+        help = "The authority to list stakes of. Defaults to your keypair."
+    )]
+    pub address: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -54,6 +108,13 @@ pub struct ClaimArgs {
         help = "The optional pool url to claim rewards from."
     )]
     pub pool_url: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "MEMO",
+        help = "A memo to attach to the claim, as some exchange deposit addresses require."
+    )]
+    pub memo: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -62,6 +123,55 @@ pub struct CloseArgs {}
 #[derive(Parser, Debug)]
 pub struct ConfigArgs {}
 
+#[derive(Parser, Debug)]
+pub struct EstimateArgs {
+    #[arg(
+        long,
+        value_name = "HASHES_PER_SEC",
+        help = "Your hashrate. If not given, a quick benchmark is run to measure it."
+    )]
+    pub hashrate: Option<f64>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "CORES_COUNT",
+        help = "The number of cores to benchmark with if no hashrate is given.",
+        default_value = "1"
+    )]
+    pub cores: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long to benchmark for if no hashrate is given.",
+        default_value = "10"
+    )]
+    pub duration: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "The number of seconds spent hashing each round.",
+        default_value = "55"
+    )]
+    pub round_time: u64,
+
+    #[arg(
+        long = "boost",
+        value_name = "MINT_ADDRESS",
+        help = "A boost you have staked in. Can be passed multiple times."
+    )]
+    pub boosts: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "The price of 1 ORE in SOL, to estimate profit."
+    )]
+    pub ore_price_sol: Option<f64>,
+}
+
 #[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
 pub struct InitializeArgs {}
@@ -77,6 +187,23 @@ pub struct MineArgs {
     )]
     pub cores: u64,
 
+    #[arg(
+        long,
+        value_name = "CORE_IDS",
+        value_delimiter = ',',
+        help = "Pin threads to these core ids, e.g. 2,4,6. Threads are assigned round-robin."
+    )]
+    pub core_list: Vec<usize>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        help = "How to pin threads to cores when no core list is given.",
+        default_value = "physical"
+    )]
+    pub pin: PinMode,
+
     #[arg(
         long,
         short,
@@ -87,25 +214,29 @@ pub struct MineArgs {
     pub buffer_time: u64,
 
     #[arg(
-        long,
+        long = "boost-mint",
+        visible_aliases = ["boost-1", "boost-2", "boost-3"],
         value_name = "MINT_ADDRESS",
-        help = "The token to apply as boost #1"
+        help = "A token to apply as boost. Can be passed multiple times."
     )]
-    pub boost_1: Option<String>,
+    pub boosts: Vec<Pubkey>,
 
     #[arg(
         long,
-        value_name = "MINT_ADDRESS",
-        help = "The token to apply as boost #2"
+        value_enum,
+        value_name = "MODE",
+        conflicts_with = "boosts",
+        help = "Pick boosts automatically from your stake accounts instead of --boost-mint."
     )]
-    pub boost_2: Option<String>,
+    pub boost: Option<BoostMode>,
 
     #[arg(
         long,
-        value_name = "MINT_ADDRESS",
-        help = "The token to apply as boost #3"
+        value_name = "ROUNDS",
+        help = "With --boost auto, the number of rounds between boost re-evaluations.",
+        default_value = "10"
     )]
-    pub boost_3: Option<String>,
+    pub boost_refresh: u64,
 
     #[arg(
         long,
@@ -114,6 +245,194 @@ pub struct MineArgs {
         help = "The optional pool url to join and forward solutions to."
     )]
     pub pool_url: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        help = "How to wind down the current round when mining is stopped.",
+        default_value = "immediate"
+    )]
+    pub cancel_mode: CancelMode,
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "Stop hashing as soon as any thread finds a hash at or above this difficulty. Solo miners still submit no earlier than the program allows.",
+        conflicts_with = "target_reward"
+    )]
+    pub target_difficulty: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "Stop hashing as soon as a hash worth at least this much ORE (before boosts) is found. Solo miners still submit no earlier than the program allows."
+    )]
+    pub target_reward: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "Keep hashing past the cutoff until the best difficulty reaches this floor."
+    )]
+    pub extend_floor: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "The max number of seconds to extend past the cutoff for the floor.",
+        requires = "extend_floor"
+    )]
+    pub extend_limit: Option<u64>,
+
+    #[arg(
+        long = "worker",
+        value_name = "HOST:PORT",
+        help = "An `ore worker` to share each round's nonce range with. Can be passed multiple times."
+    )]
+    pub workers: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "The max share of time each thread spends hashing, e.g. 50 to hash half the time.",
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    pub max_duty_cycle: Option<u8>,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Pause each thread this long after every batch of nonces.",
+        default_value = "0"
+    )]
+    pub batch_sleep_ms: u64,
+
+    #[arg(
+        long,
+        value_name = "CELSIUS",
+        help = "Pause hashing while the hottest thermal zone is at or above this temperature."
+    )]
+    pub max_temp: Option<f64>,
+
+    #[arg(
+        long,
+        help = "Start each thread at a random point of its nonce range, so restarts do not repeat work."
+    )]
+    pub randomize_nonce: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "STRATEGY",
+        help = "How to pick the bus to claim rewards from when mining solo.",
+        default_value = "highest"
+    )]
+    pub bus_strategy: BusStrategy,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "The price of 1 ORE in SOL. Enables checking that each solution pays for its fees."
+    )]
+    pub ore_price_sol: Option<f64>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "POLICY",
+        help = "What to do with a solution whose reward would not cover its fees.",
+        default_value = "delay",
+        requires = "ore_price_sol"
+    )]
+    pub unprofitable: UnprofitablePolicy,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "With --unprofitable delay or skip, the max number of seconds to keep hashing past the cutoff for a profitable hash.",
+        default_value = "10"
+    )]
+    pub profit_delay: u64,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "Claim rewards automatically once the unclaimed balance reaches this much ORE."
+    )]
+    pub auto_claim_threshold: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Claim rewards automatically this many seconds after the last claim."
+    )]
+    pub auto_claim_interval: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Claim rewards automatically once the claim fee is at most this percent of the balance.",
+        requires = "ore_price_sol"
+    )]
+    pub auto_claim_max_fee: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "WALLET_ADDRESS",
+        help = "Wallet address to receive automatically claimed tokens."
+    )]
+    pub auto_claim_to: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FRACTION",
+        help = "After each auto-claim, stake this fraction of the claimed ORE, from 0 to 1, in the ORE boost. Other boosts take tokens that claims don't pay out, so they can't be compounded into.",
+        conflicts_with = "auto_claim_to"
+    )]
+    pub auto_compound: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Append a JSON line to this file for each automatic action, like an auto-claim."
+    )]
+    pub journal: Option<String>,
+}
+
+impl Default for MineArgs {
+    fn default() -> Self {
+        MineArgs {
+            pool_url: None,
+            cores: 1,
+            core_list: vec![],
+            pin: PinMode::default(),
+            buffer_time: 0,
+            boosts: vec![],
+            boost: None,
+            boost_refresh: 10,
+            cancel_mode: CancelMode::default(),
+            target_difficulty: None,
+            target_reward: None,
+            extend_floor: None,
+            extend_limit: None,
+            workers: vec![],
+            max_duty_cycle: None,
+            batch_sleep_ms: 0,
+            max_temp: None,
+            randomize_nonce: false,
+            bus_strategy: BusStrategy::default(),
+            ore_price_sol: None,
+            unprofitable: UnprofitablePolicy::default(),
+            profit_delay: 10,
+            auto_claim_threshold: None,
+            auto_claim_interval: None,
+            auto_claim_max_fee: None,
+            auto_claim_to: None,
+            auto_compound: None,
+            journal: None,
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -191,27 +510,63 @@ pub struct StakeWithdrawArgs {
 
 #[derive(Parser, Debug)]
 pub struct TransferArgs {
-    #[arg(value_name = "AMOUNT", help = "The amount of ORE to transfer.")]
-    pub amount: f64,
+    #[arg(
+        value_name = "AMOUNT",
+        help = "The amount of ORE to transfer.",
+        required_unless_present = "batch"
+    )]
+    pub amount: Option<f64>,
 
     #[arg(
         value_name = "RECIPIENT_ADDRESS",
-        help = "The account address of the receipient."
+        help = "The account address of the receipient.",
+        required_unless_present = "batch"
+    )]
+    pub to: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "CSV_PATH",
+        help = "Transfer to every RECIPIENT_ADDRESS,AMOUNT row of this CSV file instead.",
+        conflicts_with_all = ["amount", "to"]
     )]
-    pub to: String,
+    pub batch: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "CSV_PATH",
+        help = "The file to record each batch row's outcome in, and resume from. Defaults to the batch file with a .results.csv extension.",
+        requires = "batch"
+    )]
+    pub results: Option<String>,
+
+    #[arg(
+        long,
+        help = "Resend batch rows whose earlier outcome is unknown. Only use this once you have checked they did not land.",
+        requires = "batch"
+    )]
+    pub retry_unknown: bool,
+
+    #[arg(
+        long,
+        value_name = "MEMO",
+        help = "A memo to attach to the transfer, as some exchange deposit addresses require.",
+        conflicts_with = "batch"
+    )]
+    pub memo: Option<String>,
 }
 
 #[derive(Parser, Debug)]
 pub struct UnstakeArgs {
+    #[arg(value_name = "MINT_ADDRESS", help = "The mint to unstake.")]
+    pub mint: String,
+
     #[arg(
         value_name = "AMOUNT",
         help = "The amount of the token to unstake. Defaults to max."
     )]
     pub amount: Option<f64>,
 
-    #[arg(value_name = "MINT_ADDRESS", help = "The mint to unstake.")]
-    pub mint: String,
-
     #[arg(
         long,
         value_name = "TOKEN_ACCOUNT_ADDRESS",
@@ -245,3 +600,40 @@ pub struct UpdatePoolBalanceArgs {
     )]
     pub pool_url: String,
 }
+
+#[derive(Parser, Debug)]
+pub struct WorkerArgs {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "The address to listen for coordinators on. Coordinators aren't authenticated, so only bind a network address on a trusted network.",
+        default_value = "127.0.0.1:9420"
+    )]
+    pub bind: String,
+
+    #[arg(
+        long,
+        short,
+        value_name = "CORES_COUNT",
+        help = "The number of CPU cores to allocate to mining.",
+        default_value = "1"
+    )]
+    pub cores: u64,
+
+    #[arg(
+        long,
+        value_name = "CORE_IDS",
+        value_delimiter = ',',
+        help = "Pin threads to these core ids, e.g. 2,4,6. Threads are assigned round-robin."
+    )]
+    pub core_list: Vec<usize>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        help = "How to pin threads to cores when no core list is given.",
+        default_value = "physical"
+    )]
+    pub pin: PinMode,
+}
@@ -0,0 +1,417 @@
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use colored::*;
+use ore_api::consts::MINT_ADDRESS;
+use serde::Serialize;
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey, signature::Signer};
+
+use crate::{
+    args::{ClaimArgs, MineArgs},
+    commands::{claim::ClaimOutput, stake::StakeTxOutput},
+    confirm::AutoYes,
+    cu_limits::CU_LIMIT_CLAIM,
+    error::Error,
+    journal::{self, Journal},
+    output::{status, TxOutcome, TxStatus},
+    pool::Pool,
+    token_account::TokenMint,
+    utils::{amount_f64_to_u64, amount_u64_to_f64, amount_u64_to_string},
+    Miner,
+};
+
+/// What set off an automatic claim.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimTrigger {
+    /// The unclaimed balance reached the threshold.
+    Threshold,
+    /// The interval since the last claim passed.
+    Schedule,
+    /// The claim fee dropped to the max share of the balance.
+    Fee,
+}
+
+/// When to claim mining rewards without asking. Each trigger is off unless set, and
+/// the first one that fires claims the whole unclaimed balance.
+#[derive(Clone, Debug, Default)]
+pub struct AutoClaimPolicy {
+    /// In grains.
+    pub threshold: Option<u64>,
+    pub interval: Option<Duration>,
+    /// The max claim fee, as a percent of the balance.
+    pub max_fee_percent: Option<f64>,
+    /// Needed to weigh the fee against the balance.
+    pub ore_price_sol: Option<f64>,
+    /// The wallet to claim to, defaulting to the signer's.
+    pub to: Option<Pubkey>,
+    /// The fraction of each claim to stake in the ORE boost. Claims pay out ORE, so
+    /// that's the only boost they can be compounded into.
+    pub compound: Option<f64>,
+}
+
+impl AutoClaimPolicy {
+    /// Fails if the wallet to claim to isn't a valid address.
+    pub fn from_args(args: &MineArgs) -> Result<Self, Error> {
+        Ok(Self {
+            threshold: args.auto_claim_threshold.map(amount_f64_to_u64),
+            interval: args.auto_claim_interval.map(Duration::from_secs),
+            max_fee_percent: args.auto_claim_max_fee,
+            ore_price_sol: args.ore_price_sol,
+            to: args
+                .auto_claim_to
+                .as_deref()
+                .map(Pubkey::from_str)
+                .transpose()?,
+            compound: args
+                .auto_compound
+                .map(|fraction| fraction.clamp(0f64, 1f64)),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.threshold.is_some() || self.interval.is_some() || self.max_fee_percent.is_some()
+    }
+
+    /// The trigger that fires for `balance`, in grains, with `since_last_claim` passed
+    /// and a claim fee of `fee_lamports`, if any.
+    pub fn trigger(
+        &self,
+        balance: u64,
+        since_last_claim: Duration,
+        fee_lamports: u64,
+    ) -> Option<ClaimTrigger> {
+        if balance.eq(&0) {
+            return None;
+        }
+        if self
+            .threshold
+            .is_some_and(|threshold| balance.ge(&threshold))
+        {
+            return Some(ClaimTrigger::Threshold);
+        }
+        if self
+            .interval
+            .is_some_and(|interval| since_last_claim.ge(&interval))
+        {
+            return Some(ClaimTrigger::Schedule);
+        }
+        if let (Some(max_fee_percent), Some(ore_price_sol)) =
+            (self.max_fee_percent, self.ore_price_sol)
+        {
+            let fee_ore = lamports_to_sol(fee_lamports) / ore_price_sol;
+            let fee_percent = fee_ore / amount_u64_to_f64(balance) * 100f64;
+            if fee_percent.le(&max_fee_percent) {
+                return Some(ClaimTrigger::Fee);
+            }
+        }
+        None
+    }
+}
+
+/// The grains of a `claimed` amount to stake when compounding `fraction` of it.
+pub fn compound_amount(claimed: u64, fraction: f64) -> u64 {
+    (claimed as f64 * fraction.clamp(0f64, 1f64)) as u64
+}
+
+/// An automatic claim, as recorded in the journal.
+#[derive(Clone, Debug, Serialize)]
+pub struct AutoClaim {
+    pub trigger: ClaimTrigger,
+    /// The unclaimed balance that set it off, in grains.
+    pub balance: u64,
+    #[serde(flatten)]
+    pub claim: ClaimOutput,
+}
+
+/// An automatic stake of claimed rewards, as recorded in the journal.
+#[derive(Clone, Debug, Serialize)]
+pub struct AutoCompound {
+    /// The fraction of the claim staked.
+    pub fraction: f64,
+    #[serde(flatten)]
+    pub stake: StakeTxOutput,
+}
+
+/// Applies an auto-claim policy over a mining run.
+pub struct AutoClaimer {
+    pub policy: AutoClaimPolicy,
+    pub journal: Option<Journal>,
+    last_claim_at: Instant,
+}
+
+impl AutoClaimer {
+    pub fn new(policy: AutoClaimPolicy, journal: Option<Journal>) -> Self {
+        Self {
+            policy,
+            journal,
+            last_claim_at: Instant::now(),
+        }
+    }
+
+    /// Claims `balance` from the signer's proof or, given a pool url, its pool member
+    /// account, if the policy says so.
+    pub async fn check(
+        &mut self,
+        miner: &Miner,
+        balance: u64,
+        pool_url: Option<String>,
+    ) -> Option<AutoClaim> {
+        if !self.policy.is_enabled() {
+            return None;
+        }
+
+        // Check the triggers
+        let fee_lamports = match self.policy.max_fee_percent {
+            Some(_) => miner.estimate_tx_cost(CU_LIMIT_CLAIM).await.lamports(),
+            None => 0,
+        };
+        let trigger = self
+            .policy
+            .trigger(balance, self.last_claim_at.elapsed(), fee_lamports)?;
+
+        // Claim without asking
+        let miner = miner.clone().with_confirmer(Arc::new(AutoYes));
+        let args = ClaimArgs {
+            amount: None,
+            to: self.policy.to.map(|to| to.to_string()),
+            pool_url: pool_url.clone(),
+            memo: None,
+        };
+        let claim = match miner.claim_rewards(args).await {
+            Ok(claim) => claim,
+            Err(err) => ClaimOutput {
+                amount: balance,
+                beneficiary: self.policy.to.map(|to| to.to_string()).unwrap_or_default(),
+                tx: TxOutcome::failed(format!("{:?}", err)),
+            },
+        };
+        match claim.tx.status {
+            TxStatus::Confirmed => {
+                self.last_claim_at = Instant::now();
+                status(format!(
+                    "Auto-claimed {} ORE to {} ({:?})",
+                    amount_u64_to_string(claim.amount),
+                    claim.beneficiary,
+                    trigger
+                ));
+            }
+            _ => status(format!(
+                "{} Auto-claim failed: {}",
+                "WARNING".bold().yellow(),
+                claim.tx.error.clone().unwrap_or_default()
+            )),
+        }
+        let auto_claim = AutoClaim {
+            trigger,
+            balance,
+            claim,
+        };
+        journal::record(self.journal.as_ref(), "auto_claim", &auto_claim);
+
+        // Stake part of the claim, if set
+        if auto_claim.claim.tx.status.eq(&TxStatus::Confirmed) {
+            if let Some(fraction) = self.policy.compound {
+                self.compound(&miner, &auto_claim.claim, fraction, pool_url)
+                    .await;
+            }
+        }
+        Some(auto_claim)
+    }
+
+    /// Stakes `fraction` of a claim in the ORE boost, through the pool when pool mining.
+    async fn compound(
+        &self,
+        miner: &Miner,
+        claim: &ClaimOutput,
+        fraction: f64,
+        pool_url: Option<String>,
+    ) -> Option<AutoCompound> {
+        let amount = compound_amount(claim.amount, fraction);
+        if amount.eq(&0) {
+            return None;
+        }
+
+        // Only stake what was claimed to the signer's own wallet
+        let mint = TokenMint::fetch(&miner.rpc_client, MINT_ADDRESS).await;
+        let sender = mint.as_ref().map(|mint| mint.ata(&miner.signer().pubkey()));
+        if sender.is_ok_and(|sender| claim.beneficiary.ne(&sender.to_string())) {
+            status(format!(
+                "{} Not auto-compounding rewards claimed to another wallet",
+                "WARNING".bold().yellow()
+            ));
+            return None;
+        }
+
+        // Deposit
+        let result = match (mint, pool_url) {
+            (Err(err), _) => Err(err),
+            (Ok(mint), None) => miner.deposit_stake(mint.address, amount).await,
+            (Ok(mint), Some(pool_url)) => {
+                let pool = Pool {
+                    http_client: reqwest::Client::new(),
+                    pool_url,
+                };
+                let sender = mint.ata(&miner.signer().pubkey());
+                miner.deposit_pool_stake(&pool, &mint, sender, amount).await
+            }
+        };
+        let stake = match result {
+            Ok(stake) => stake,
+            Err(err) => StakeTxOutput {
+                mint: MINT_ADDRESS.to_string(),
+                amount,
+                tx: TxOutcome::failed(format!("{:?}", err)),
+            },
+        };
+        match stake.tx.status {
+            TxStatus::Confirmed => status(format!(
+                "Auto-compounded {} ORE",
+                amount_u64_to_string(stake.amount)
+            )),
+            _ => status(format!(
+                "{} Auto-compound failed: {}",
+                "WARNING".bold().yellow(),
+                stake.tx.error.clone().unwrap_or_default()
+            )),
+        }
+        let auto_compound = AutoCompound { fraction, stake };
+        journal::record(self.journal.as_ref(), "auto_compound", &auto_compound);
+        Some(auto_compound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORE: u64 = 100_000_000_000;
+
+    #[test]
+    fn nothing_triggers_without_a_balance() {
+        let policy = AutoClaimPolicy {
+            threshold: Some(0),
+            interval: Some(Duration::ZERO),
+            max_fee_percent: Some(100.0),
+            ore_price_sol: Some(1.0),
+            ..Default::default()
+        };
+        assert_eq!(policy.trigger(0, Duration::from_secs(60), 0), None);
+        assert_eq!(
+            AutoClaimPolicy::default().trigger(ORE, Duration::from_secs(60), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn threshold_triggers_at_the_balance() {
+        let policy = AutoClaimPolicy {
+            threshold: Some(ORE),
+            ..Default::default()
+        };
+        assert_eq!(policy.trigger(ORE - 1, Duration::ZERO, 0), None);
+        assert_eq!(
+            policy.trigger(ORE, Duration::ZERO, 0),
+            Some(ClaimTrigger::Threshold)
+        );
+    }
+
+    #[test]
+    fn schedule_triggers_after_the_interval() {
+        let policy = AutoClaimPolicy {
+            interval: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        assert_eq!(policy.trigger(1, Duration::from_secs(3599), 0), None);
+        assert_eq!(
+            policy.trigger(1, Duration::from_secs(3600), 0),
+            Some(ClaimTrigger::Schedule)
+        );
+    }
+
+    #[test]
+    fn fee_triggers_once_it_is_a_small_enough_share() {
+        let policy = AutoClaimPolicy {
+            max_fee_percent: Some(1.0),
+            ore_price_sol: Some(0.5),
+            ..Default::default()
+        };
+        // 5_000_000 lamports is 0.01 ORE at 0.5 SOL per ORE
+        assert_eq!(policy.trigger(ORE / 2, Duration::ZERO, 5_000_000), None);
+        assert_eq!(
+            policy.trigger(ORE, Duration::ZERO, 5_000_000),
+            Some(ClaimTrigger::Fee)
+        );
+
+        // Without a price the fee can't be weighed
+        let unpriced = AutoClaimPolicy {
+            ore_price_sol: None,
+            ..policy
+        };
+        assert_eq!(unpriced.trigger(ORE, Duration::ZERO, 0), None);
+    }
+
+    #[test]
+    fn threshold_wins_over_the_other_triggers() {
+        let policy = AutoClaimPolicy {
+            threshold: Some(ORE),
+            interval: Some(Duration::ZERO),
+            max_fee_percent: Some(100.0),
+            ore_price_sol: Some(1.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.trigger(ORE, Duration::ZERO, 0),
+            Some(ClaimTrigger::Threshold)
+        );
+        assert_eq!(
+            policy.trigger(1, Duration::ZERO, 0),
+            Some(ClaimTrigger::Schedule)
+        );
+    }
+
+    #[test]
+    fn compounding_stakes_the_fraction_of_the_claim() {
+        assert_eq!(compound_amount(ORE, 0.25), ORE / 4);
+        assert_eq!(compound_amount(ORE, 1.0), ORE);
+        assert_eq!(compound_amount(ORE, 1.5), ORE);
+        assert_eq!(compound_amount(ORE, 0.0), 0);
+        assert_eq!(compound_amount(ORE, -1.0), 0);
+        assert_eq!(compound_amount(3, 0.5), 1);
+    }
+
+    #[test]
+    fn compound_fraction_is_clamped_from_args() {
+        let args = MineArgs {
+            auto_compound: Some(2.0),
+            auto_claim_threshold: Some(1.5),
+            ..Default::default()
+        };
+        let policy = AutoClaimPolicy::from_args(&args).unwrap();
+        assert_eq!(policy.compound, Some(1.0));
+        assert_eq!(policy.threshold, Some(ORE + ORE / 2));
+        assert!(policy.is_enabled());
+    }
+
+    #[test]
+    fn claim_wallet_is_parsed_from_args() {
+        let wallet = Pubkey::new_unique();
+        let args = MineArgs {
+            auto_claim_to: Some(wallet.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(AutoClaimPolicy::from_args(&args).unwrap().to, Some(wallet));
+
+        let args = MineArgs {
+            auto_claim_to: Some("not-a-wallet".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            AutoClaimPolicy::from_args(&args),
+            Err(Error::SolanaParsePubkey(_))
+        ));
+    }
+}
@@ -0,0 +1,205 @@
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    commands::boosts::{BoostInfo, StakePosition},
+    error::Error,
+};
+
+/// The number of boosts a mine transaction can carry. Everything else handles any
+/// number of boosts, so supporting more slots only needs this raised.
+pub const MAX_BOOSTS: usize = 3;
+
+/// How the solo miner picks the boosts it applies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum BoostMode {
+    /// The unexpired boosts with the highest effective multiplier among your stakes.
+    Auto,
+}
+
+/// Decides when automatic boost selection is due, every `refresh_rounds` rounds.
+#[derive(Clone, Debug)]
+pub struct BoostSelector {
+    pub refresh_rounds: u64,
+    rounds_left: u64,
+}
+
+impl BoostSelector {
+    pub fn new(refresh_rounds: u64) -> Self {
+        Self {
+            refresh_rounds: refresh_rounds.max(1),
+            rounds_left: 0,
+        }
+    }
+
+    /// Counts a round, returning true on the first and every `refresh_rounds` after.
+    pub fn tick(&mut self) -> bool {
+        let due = self.rounds_left.eq(&0);
+        self.rounds_left = if due {
+            self.refresh_rounds - 1
+        } else {
+            self.rounds_left - 1
+        };
+        due
+    }
+}
+
+/// The effective multiplier of a stake, `multiplier × stake / total_stake`.
+pub fn effective_multiplier(boost: &BoostInfo, stake: &StakePosition) -> f64 {
+    boost.multiplier as f64 * stake.share
+}
+
+/// The mints of the `count` unexpired boosts with the highest effective multiplier
+/// among `stakes`. Pool shares are skipped since they don't boost a solo miner.
+pub fn rank_boosts(
+    boosts: &[BoostInfo],
+    stakes: &[StakePosition],
+    now: i64,
+    count: usize,
+) -> Vec<Pubkey> {
+    let mut ranked: Vec<(Pubkey, f64)> = stakes
+        .iter()
+        .filter(|stake| stake.pool.is_none() && stake.balance.gt(&0))
+        .filter_map(|stake| {
+            let boost = boosts.iter().find(|boost| boost.mint == stake.mint)?;
+            if boost.expires_at.le(&now) {
+                return None;
+            }
+            let mint = Pubkey::from_str(&boost.mint).ok()?;
+            Some((mint, effective_multiplier(boost, stake)))
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+        .into_iter()
+        .take(count)
+        .map(|(mint, _)| mint)
+        .collect()
+}
+
+/// Parses boost mints, as listed in profiles or passed by embedders, skipping blanks.
+pub fn parse_boosts<'a>(boosts: impl IntoIterator<Item = &'a str>) -> Result<Vec<Pubkey>, Error> {
+    boosts
+        .into_iter()
+        .map(str::trim)
+        .filter(|mint| !mint.is_empty())
+        .map(|mint| {
+            Pubkey::from_str(mint)
+                .map_err(|_| Error::InvalidBoost(mint.to_string(), "not an address".to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boost(mint: &Pubkey, multiplier: u64, expires_at: i64) -> BoostInfo {
+        BoostInfo {
+            address: Pubkey::new_unique().to_string(),
+            mint: mint.to_string(),
+            symbol: None,
+            decimals: 11,
+            multiplier,
+            total_stake: 1_000,
+            expires_at,
+        }
+    }
+
+    fn stake(mint: &Pubkey, share: f64, pool: Option<&str>) -> StakePosition {
+        StakePosition {
+            mint: mint.to_string(),
+            symbol: None,
+            address: Pubkey::new_unique().to_string(),
+            balance: (share * 1_000.0) as u64,
+            share,
+            pool: pool.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn tick_is_due_on_the_first_round_and_every_refresh_after() {
+        let mut selector = BoostSelector::new(3);
+        let due: Vec<bool> = (0..7).map(|_| selector.tick()).collect();
+        assert_eq!(due, vec![true, false, false, true, false, false, true]);
+
+        let mut every_round = BoostSelector::new(0);
+        assert_eq!(every_round.refresh_rounds, 1);
+        assert!((0..3).all(|_| every_round.tick()));
+    }
+
+    #[test]
+    fn rank_orders_by_effective_multiplier_and_truncates() {
+        let mints: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let boosts = vec![
+            boost(&mints[0], 2, 100),
+            boost(&mints[1], 8, 100),
+            boost(&mints[2], 4, 100),
+            boost(&mints[3], 16, 100),
+        ];
+        let stakes = vec![
+            stake(&mints[0], 0.5, None),
+            stake(&mints[1], 0.5, None),
+            stake(&mints[2], 0.5, None),
+            stake(&mints[3], 0.1, None),
+        ];
+        // Effective multipliers are 1, 4, 2 and 1.6
+        assert_eq!(
+            rank_boosts(&boosts, &stakes, 0, 3),
+            vec![mints[1], mints[2], mints[3]]
+        );
+        assert_eq!(rank_boosts(&boosts, &stakes, 0, 1), vec![mints[1]]);
+        assert!(rank_boosts(&boosts, &stakes, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn rank_skips_expired_pooled_and_empty_stakes() {
+        let mints: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let boosts = vec![
+            boost(&mints[0], 8, 50),
+            boost(&mints[1], 8, 100),
+            boost(&mints[2], 8, 100),
+            boost(&mints[3], 2, 100),
+        ];
+        let stakes = vec![
+            stake(&mints[0], 0.5, None),
+            stake(&mints[1], 0.5, Some("pool")),
+            stake(&mints[2], 0.0, None),
+            stake(&mints[3], 0.5, None),
+            // No boost for this stake
+            stake(&mints[4], 0.5, None),
+        ];
+        assert_eq!(rank_boosts(&boosts, &stakes, 50, 3), vec![mints[3]]);
+        assert_eq!(
+            rank_boosts(&boosts, &stakes, 49, 3),
+            vec![mints[0], mints[3]]
+        );
+    }
+
+    #[test]
+    fn parse_boosts_skips_blanks_and_rejects_bad_mints() {
+        let mint = Pubkey::new_unique();
+        let list = format!(" {} ,,", mint);
+        assert_eq!(parse_boosts(list.split(',')).unwrap(), vec![mint]);
+        assert!(parse_boosts("".split(',')).unwrap().is_empty());
+        assert!(matches!(
+            parse_boosts(["nope"]),
+            Err(Error::InvalidBoost(mint, _)) if mint == "nope"
+        ));
+    }
+
+    #[test]
+    fn auto_boosts_conflict_with_explicit_mints() {
+        use clap::Parser;
+
+        use crate::args::MineArgs;
+
+        let mint = Pubkey::new_unique().to_string();
+        let args = MineArgs::try_parse_from(["mine", "--boost", "auto"]).unwrap();
+        assert_eq!(args.boost, Some(BoostMode::Auto));
+        assert!(MineArgs::try_parse_from(["mine", "--boost-mint", &mint]).is_ok());
+        assert!(MineArgs::try_parse_from(["mine", "--boost", "auto", "--boost-1", &mint]).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use std::cmp::Reverse;
+
+use clap::ValueEnum;
+use ore_api::{consts::BUS_COUNT, state::Bus};
+use rand::{distributions::WeightedIndex, prelude::*};
+
+/// How the solo miner picks the bus to claim its reward from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum BusStrategy {
+    /// The bus with the most rewards left.
+    #[default]
+    Highest,
+
+    /// A random bus, weighted by the rewards it has left.
+    Weighted,
+
+    /// The bus this miner used least recently, preferring the most rewards on ties.
+    Lru,
+
+    /// A random bus among those that can cover the expected reward.
+    CanCover,
+}
+
+/// The bus picked for a round.
+#[derive(Clone, Copy, Debug)]
+pub struct BusChoice {
+    pub id: usize,
+    pub rewards: Option<u64>,
+}
+
+/// Picks a bus per round according to its strategy, remembering which busses it used.
+#[derive(Clone, Debug, Default)]
+pub struct BusSelector {
+    pub strategy: BusStrategy,
+    round: u64,
+    last_used: [Option<u64>; BUS_COUNT],
+}
+
+impl BusSelector {
+    pub fn new(strategy: BusStrategy) -> Self {
+        Self {
+            strategy,
+            ..Default::default()
+        }
+    }
+
+    /// Picks one of `busses`, or a random bus if none could be fetched.
+    pub fn select(&mut self, busses: &[Bus], expected_reward: u64) -> BusChoice {
+        let mut rng = rand::thread_rng();
+        let highest = || busses.iter().max_by_key(|bus| bus.rewards);
+        let bus = match self.strategy {
+            BusStrategy::Highest => highest(),
+            BusStrategy::Weighted => WeightedIndex::new(busses.iter().map(|bus| bus.rewards))
+                .ok()
+                .map(|weights| &busses[weights.sample(&mut rng)])
+                .or_else(highest),
+            BusStrategy::Lru => busses
+                .iter()
+                .filter(|bus| bus.rewards.gt(&0))
+                .min_by_key(|bus| {
+                    (
+                        self.last_used[bus.id as usize % BUS_COUNT],
+                        Reverse(bus.rewards),
+                    )
+                })
+                .or_else(highest),
+            BusStrategy::CanCover => busses
+                .iter()
+                .filter(|bus| bus.rewards.ge(&expected_reward))
+                .collect::<Vec<_>>()
+                .choose(&mut rng)
+                .copied()
+                .or_else(highest),
+        };
+
+        // Fall back to a random bus
+        let choice = match bus {
+            Some(bus) => BusChoice {
+                id: bus.id as usize % BUS_COUNT,
+                rewards: Some(bus.rewards),
+            },
+            None => BusChoice {
+                id: rng.gen_range(0..BUS_COUNT),
+                rewards: None,
+            },
+        };
+        self.round += 1;
+        self.last_used[choice.id] = Some(self.round);
+        choice
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn busses(rewards: &[u64]) -> Vec<Bus> {
+        rewards
+            .iter()
+            .enumerate()
+            .map(|(id, rewards)| Bus {
+                id: id as u64,
+                rewards: *rewards,
+                theoretical_rewards: 0,
+                top_balance: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn highest_picks_the_most_rewards() {
+        let mut selector = BusSelector::new(BusStrategy::Highest);
+        let choice = selector.select(&busses(&[10, 50, 30]), 0);
+        assert_eq!(choice.id, 1);
+        assert_eq!(choice.rewards, Some(50));
+    }
+
+    #[test]
+    fn weighted_never_picks_empty_busses() {
+        let mut selector = BusSelector::new(BusStrategy::Weighted);
+        let funded = busses(&[0, 40, 0, 60]);
+        for _ in 0..100 {
+            let choice = selector.select(&funded, 0);
+            assert!([1, 3].contains(&choice.id));
+        }
+
+        // All empty falls back to the highest
+        let choice = selector.select(&busses(&[0, 0]), 0);
+        assert_eq!(choice.rewards, Some(0));
+    }
+
+    #[test]
+    fn lru_rotates_through_busses_with_rewards() {
+        let mut selector = BusSelector::new(BusStrategy::Lru);
+        let busses = busses(&[10, 30, 0, 20]);
+        let picks: Vec<usize> = (0..4).map(|_| selector.select(&busses, 0).id).collect();
+        assert_eq!(picks, vec![1, 3, 0, 1]);
+    }
+
+    #[test]
+    fn can_cover_only_picks_busses_with_enough_left() {
+        let mut selector = BusSelector::new(BusStrategy::CanCover);
+        let busses = busses(&[10, 30, 5, 25]);
+        for _ in 0..100 {
+            let choice = selector.select(&busses, 20);
+            assert!([1, 3].contains(&choice.id));
+        }
+
+        // Nothing covers it, so take the highest
+        assert_eq!(selector.select(&busses, 100).id, 1);
+    }
+
+    #[test]
+    fn no_busses_falls_back_to_a_random_one() {
+        for strategy in [
+            BusStrategy::Highest,
+            BusStrategy::Weighted,
+            BusStrategy::Lru,
+            BusStrategy::CanCover,
+        ] {
+            let choice = BusSelector::new(strategy).select(&[], 0);
+            assert!(choice.id.lt(&BUS_COUNT));
+            assert_eq!(choice.rewards, None);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use serde::Serialize;
 use solana_program::pubkey::Pubkey;
 use solana_sdk::signature::Signer;
 use steel::AccountDeserialize;
@@ -7,11 +8,33 @@ use steel::AccountDeserialize;
 use crate::{
     args::BalanceArgs,
     error::Error,
-    pool::Pool,
-    utils::{self, amount_u64_to_string, get_proof_with_authority},
     miner::Miner,
+    output,
+    pool::Pool,
+    utils::{self, amount_u64_to_string, proof_pubkey},
 };
 
+/// The ORE held by an authority, in grains.
+#[derive(Clone, Debug, Serialize)]
+pub struct Balances {
+    pub authority: String,
+    /// Held in the authority's ORE token account.
+    pub wallet: u64,
+    /// Held in the authority's proof.
+    pub stake: u64,
+}
+
+/// A member's balance in a pool, in grains.
+#[derive(Clone, Debug, Serialize)]
+pub struct PoolBalance {
+    pub pool: String,
+    pub member: String,
+    /// Attributed to the member on-chain.
+    pub balance: u64,
+    /// Earned but not yet attributed on-chain.
+    pub pending: u64,
+}
+
 impl Miner {
     pub async fn balance(&self, args: BalanceArgs) {
         match args.pool_url {
@@ -20,75 +43,112 @@ impl Miner {
             }
             Some(ref pool_url) => {
                 if let Err(err) = self.balance_pool(pool_url).await {
-                    println!("{:?}", err);
+                    output::print_error(err);
                 }
             }
         }
     }
+
     async fn balance_pool(&self, pool_url: &String) -> Result<(), Error> {
-        let signer = self.signer();
-        // build pool client
-        let pool = Pool {
-            http_client: reqwest::Client::new(),
-            pool_url: pool_url.clone(),
-        };
-        // fetch pool address
-        let pool_address = pool.get_pool_address().await?;
-        // fetch on-chain balance
-        let (member_pda, _) =
-            ore_pool_api::state::member_pda(signer.pubkey(), pool_address.address);
-        let member_data = self.rpc_client.get_account_data(&member_pda).await?;
-        let member = ore_pool_api::state::Member::try_from_bytes(member_data.as_slice())?;
-        println!("//////////////////////////////");
-        println!(
-            "your on-chain pool balance: {:?}",
-            utils::amount_u64_to_string(member.balance)
-        );
-        // fetch db balance
-        let member_db = pool.get_pool_member(&self).await?;
-        let diff = (member_db.total_balance as u64) - member.total_balance;
-        if diff.gt(&0) {
+        let balance = self.get_pool_balance(pool_url).await?;
+        output::print(&balance, |balance| {
             println!("//////////////////////////////");
             println!(
-                "you have an amount pending on-chain attribution: {:?}",
-                utils::amount_u64_to_string(diff)
+                "your on-chain pool balance: {:?}",
+                utils::amount_u64_to_string(balance.balance)
             );
-            println!("the pool operator automatically attributes your on-chain balance at regular intervals.");
-            println!("if you want to attribute this balance yourself now, you can pay the transaction fee by running the 'ore update-pool-balance {}' command.", pool_url);
-            println!("for more info run the 'ore help' command.");
-        }
+            if balance.pending.gt(&0) {
+                println!("//////////////////////////////");
+                println!(
+                    "you have an amount pending on-chain attribution: {:?}",
+                    utils::amount_u64_to_string(balance.pending)
+                );
+                println!("the pool operator automatically attributes your on-chain balance at regular intervals.");
+                println!("if you want to attribute this balance yourself now, you can pay the transaction fee by running the 'ore update-pool-balance {}' command.", pool_url);
+                println!("for more info run the 'ore help' command.");
+            }
+        });
         Ok(())
     }
+
     async fn balance_solo(&self, args: &BalanceArgs) {
-        let signer = self.signer();
-        let address = if let Some(address) = &args.address {
+        let authority = if let Some(address) = &args.address {
             if let Ok(address) = Pubkey::from_str(&address) {
-                address
+                Some(address)
             } else {
-                println!("Invalid address: {:?}", address);
+                output::print_error(format_args!("Invalid address: {:?}", address));
                 return;
             }
         } else {
-            signer.pubkey()
+            None
         };
-        let proof = get_proof_with_authority(&self.rpc_client, address).await;
+        match self.get_balances(authority).await {
+            Ok(balances) => output::print(&balances, |balances| {
+                println!(
+                    "Balance: {} ORE\nStake: {} ORE",
+                    amount_u64_to_string(balances.wallet),
+                    amount_u64_to_string(balances.stake)
+                )
+            }),
+            Err(err) => output::print_error(err),
+        }
+    }
+
+    /// The wallet and proof balances of `authority`, defaulting to the signer.
+    /// Missing accounts count as empty.
+    pub async fn get_balances(&self, authority: Option<Pubkey>) -> Result<Balances, Error> {
+        let authority = authority.unwrap_or_else(|| self.signer().pubkey());
         let token_account_address = spl_associated_token_account::get_associated_token_address(
-            &address,
+            &authority,
             &ore_api::consts::MINT_ADDRESS,
         );
-        let token_balance = if let Ok(Some(token_account)) = self
+        let wallet = match self
             .rpc_client
             .get_token_account(&token_account_address)
-            .await
+            .await?
         {
-            token_account.token_amount.ui_amount_string
-        } else {
-            "0".to_string()
+            Some(token_account) => u64::from_str(&token_account.token_amount.amount)?,
+            None => 0,
+        };
+        let proof_account = self
+            .rpc_client
+            .get_account_with_commitment(&proof_pubkey(authority), self.rpc_client.commitment())
+            .await?
+            .value;
+        let stake = match proof_account {
+            Some(account) => ore_api::state::Proof::try_from_bytes(&account.data)?.balance,
+            None => 0,
+        };
+        Ok(Balances {
+            authority: authority.to_string(),
+            wallet,
+            stake,
+        })
+    }
+
+    /// The signer's balance in the pool at `pool_url`.
+    pub async fn get_pool_balance(&self, pool_url: &str) -> Result<PoolBalance, Error> {
+        let signer = self.signer();
+        // build pool client
+        let pool = Pool {
+            http_client: reqwest::Client::new(),
+            pool_url: pool_url.to_string(),
         };
-        println!(
-            "Balance: {} ORE\nStake: {} ORE",
-            token_balance,
-            amount_u64_to_string(proof.balance)
-        )
+        // fetch pool address
+        let pool_address = pool.get_pool_address().await?;
+        // fetch on-chain balance
+        let (member_pda, _) =
+            ore_pool_api::state::member_pda(signer.pubkey(), pool_address.address);
+        let member_data = self.rpc_client.get_account_data(&member_pda).await?;
+        let member = ore_pool_api::state::Member::try_from_bytes(member_data.as_slice())?;
+        // fetch db balance
+        let member_db = pool.get_pool_member(&self).await?;
+        let pending = (member_db.total_balance as u64).saturating_sub(member.total_balance);
+        Ok(PoolBalance {
+            pool: pool_address.address.to_string(),
+            member: member_pda.to_string(),
+            balance: member.balance,
+            pending,
+        })
     }
 }
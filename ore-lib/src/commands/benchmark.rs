@@ -1,80 +1,166 @@
-use std::{sync::Arc, time::Instant};
+use std::sync::Arc;
 
-use drillx::equix;
+use serde::Serialize;
 use solana_rpc_client::spinner;
 
-use crate::{args::BenchmarkArgs, Miner};
+use crate::{
+    args::BenchmarkArgs,
+    control::MiningControl,
+    core_alloc::CoreAllocation,
+    error::Error,
+    hash_backend::{CpuBackend, HashJob, HashProgress, StopPolicy},
+    nonce::NonceAllocator,
+    output,
+    throttle::Throttle,
+    Miner,
+};
 
-const TEST_DURATION: i64 = 30;
+/// The outcome of benchmarking one core count.
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchmarkResult {
+    pub cores: u64,
+    pub duration: u64,
+    pub hashes: u64,
+    /// In hashes per second.
+    pub hashrate: u64,
+    pub per_core: Vec<u64>,
+    pub best_difficulty: u32,
+    /// The number of hashes found per difficulty.
+    pub histogram: Vec<(u32, u64)>,
+}
 
 impl Miner {
     pub async fn benchmark(&self, args: BenchmarkArgs) {
         // Check num threads
         self.check_num_cores(args.cores);
+        let challenge = match parse_challenge(args.challenge.as_deref()) {
+            Ok(challenge) => challenge,
+            Err(err) => {
+                output::print_error(format_args!("{}", err));
+                return;
+            }
+        };
 
-        // Dispatch job to each thread
-        let challenge = [0; 32];
-        let progress_bar = Arc::new(spinner::new_progress_bar());
-        progress_bar.set_message(format!(
-            "Benchmarking. This will take {} sec...",
-            TEST_DURATION
-        ));
-        let core_ids = core_affinity::get_core_ids().unwrap();
-        let handles: Vec<_> = core_ids
-            .into_iter()
-            .map(|i| {
-                std::thread::spawn({
-                    move || {
-                        let timer = Instant::now();
-                        let first_nonce = u64::MAX
-                            .saturating_div(args.cores)
-                            .saturating_mul(i.id as u64);
-                        let mut nonce = first_nonce;
-                        let mut memory = equix::SolverMemory::new();
-                        loop {
-                            // Return if core should not be used
-                            if (i.id as u64).ge(&args.cores) {
-                                return 0;
-                            }
-
-                            // Pin to core
-                            let _ = core_affinity::set_for_current(i);
-
-                            // Create hash
-                            let _hx = drillx::hash_with_memory(
-                                &mut memory,
-                                &challenge,
-                                &nonce.to_le_bytes(),
-                            );
+        // Benchmark each core count
+        let core_counts = if args.sweep {
+            (1..=args.cores).collect()
+        } else {
+            vec![args.cores]
+        };
+        let mut results = vec![];
+        for cores in core_counts {
+            let progress_bar = Arc::new(spinner::new_progress_bar());
+            progress_bar.set_message(format!(
+                "Benchmarking {} core(s). This will take {} sec...",
+                cores, args.duration
+            ));
+            let result = self.run_benchmark(
+                CpuBackend::new(
+                    CoreAllocation::new(&args.core_list, args.pin),
+                    Throttle::default(),
+                ),
+                cores,
+                args.duration,
+                challenge,
+            );
+            progress_bar.finish_with_message(format!(
+                "Hashpower ({} core(s)): {} H/sec",
+                cores, result.hashrate
+            ));
+            results.push(result);
+        }
 
-                            // Increment nonce
-                            nonce += 1;
+        // Print results
+        output::print(&results, |results| {
+            if args.sweep {
+                log_scaling(results);
+            } else if let Some(result) = results.first() {
+                log_result(result);
+            }
+        });
+    }
 
-                            // Exit if time has elapsed
-                            if (timer.elapsed().as_secs() as i64).ge(&TEST_DURATION) {
-                                break;
-                            }
-                        }
+    /// Hashes `challenge` on `cores` threads for `seconds` and reports the hashrate.
+    pub fn run_benchmark(
+        &self,
+        cpu: CpuBackend,
+        cores: u64,
+        seconds: u64,
+        challenge: [u8; 32],
+    ) -> BenchmarkResult {
+        let seconds = seconds.max(1);
+        let job = HashJob {
+            challenge,
+            nonce_ranges: NonceAllocator::solo(false).thread_ranges(cores),
+            policy: StopPolicy::timed(seconds),
+        };
+        let control = MiningControl::new();
+        control.start();
+        let progress = HashProgress::default();
+        let result = self.backend(cpu).run(&job, &progress, &control);
+        BenchmarkResult {
+            cores,
+            duration: seconds,
+            hashes: result.hashes,
+            hashrate: result.hashes / seconds,
+            per_core: progress
+                .thread_hashes()
+                .iter()
+                .map(|hashes| hashes / seconds)
+                .collect(),
+            best_difficulty: result.difficulty,
+            histogram: progress.histogram(),
+        }
+    }
+}
 
-                        // Return hash count
-                        nonce - first_nonce
-                    }
-                })
-            })
-            .collect();
+fn parse_challenge(challenge: Option<&str>) -> Result<[u8; 32], Error> {
+    match challenge {
+        None => Ok([0; 32]),
+        Some("random") => Ok(rand::random()),
+        Some(challenge) => bs58::decode(challenge)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| Error::Internal("challenge must be 32 bytes in base58".to_string())),
+    }
+}
 
-        // Join handles and return best nonce
-        let mut total_nonces = 0;
-        for h in handles {
-            if let Ok(count) = h.join() {
-                total_nonces += count;
-            }
-        }
+fn log_result(result: &BenchmarkResult) {
+    for (core, hashrate) in result.per_core.iter().enumerate() {
+        println!("  Core {}: {} H/sec", core, hashrate);
+    }
+    println!("Best difficulty: {}", result.best_difficulty);
+    println!("Difficulty histogram:");
+    let max = result
+        .histogram
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(1);
+    for (difficulty, count) in result.histogram.iter() {
+        let width = ((*count as f64 / max as f64) * 40.0).ceil() as usize;
+        println!("  {:>3}: {:>10} {}", difficulty, count, "#".repeat(width));
+    }
+}
 
-        // Update log
-        progress_bar.finish_with_message(format!(
-            "Hashpower: {} H/sec",
-            total_nonces.saturating_div(TEST_DURATION as u64),
-        ));
+fn log_scaling(results: &[BenchmarkResult]) {
+    let base = results
+        .first()
+        .map(|result| result.hashrate.max(1))
+        .unwrap_or(1);
+    println!(
+        "{:>5} {:>10} {:>10} {:>8}",
+        "Cores", "H/sec", "H/sec/core", "Scaling"
+    );
+    for result in results {
+        let scaling = result.hashrate as f64 / (base * result.cores) as f64;
+        println!(
+            "{:>5} {:>10} {:>10} {:>7.0}%",
+            result.cores,
+            result.hashrate,
+            result.hashrate / result.cores.max(1),
+            scaling * 100.0
+        );
     }
 }
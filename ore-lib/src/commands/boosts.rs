@@ -0,0 +1,294 @@
+use std::{collections::HashMap, str::FromStr};
+
+use colored::*;
+use mpl_token_metadata::accounts::Metadata;
+use ore_boost_api::state::{Boost, Stake};
+use ore_pool_api::state::Share;
+use serde::Serialize;
+use solana_client::{
+    rpc_config::RpcProgramAccountsConfig,
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{account::Account, signature::Signer};
+use spl_token::amount_to_ui_amount;
+use spl_token_2022::{extension::StateWithExtensions, state::Mint};
+use steel::{AccountDeserialize, Discriminator};
+
+use crate::{args::BoostsArgs, boost_select::rank_boosts, error::Error, output, Miner};
+
+/// A boost account, with amounts in the mint's base units.
+#[derive(Clone, Debug, Serialize)]
+pub struct BoostInfo {
+    pub address: String,
+    pub mint: String,
+    /// The token symbol from the mint's metadata, if it has any.
+    pub symbol: Option<String>,
+    pub decimals: u8,
+    pub multiplier: u64,
+    pub total_stake: u64,
+    pub expires_at: i64,
+}
+
+/// A stake in a boost, held directly or through a pool.
+#[derive(Clone, Debug, Serialize)]
+pub struct StakePosition {
+    pub mint: String,
+    pub symbol: Option<String>,
+    /// The stake account, or the pool share account for pool stake.
+    pub address: String,
+    pub balance: u64,
+    /// The fraction of the boost's total stake this accounts for.
+    pub share: f64,
+    /// The pool holding the stake, if any.
+    pub pool: Option<String>,
+}
+
+/// All boosts and an authority's stakes in them.
+#[derive(Clone, Debug, Serialize)]
+pub struct BoostList {
+    pub authority: String,
+    pub boosts: Vec<BoostInfo>,
+    pub stakes: Vec<StakePosition>,
+}
+
+impl Miner {
+    pub async fn boosts(&self, args: BoostsArgs) {
+        let authority = match &args.address {
+            Some(address) => match Pubkey::from_str(address) {
+                Ok(authority) => authority,
+                Err(err) => return output::print_error(err),
+            },
+            None => self.signer().pubkey(),
+        };
+        let list = match self.get_boosts().await {
+            Ok(boosts) => match self.get_stakes(authority, &boosts).await {
+                Ok(stakes) => BoostList {
+                    authority: authority.to_string(),
+                    boosts,
+                    stakes,
+                },
+                Err(err) => return output::print_error(err),
+            },
+            Err(err) => return output::print_error(err),
+        };
+        output::print(&list, log_boost_list);
+    }
+
+    /// All boost accounts, with their mints' decimals and symbols.
+    pub async fn get_boosts(&self) -> Result<Vec<BoostInfo>, Error> {
+        let mut boosts = self
+            .get_program_accounts::<Boost>(ore_boost_api::ID, None)
+            .await?
+            .into_iter()
+            .map(|(address, boost)| (address, boost.mint, boost))
+            .collect::<Vec<_>>();
+        boosts.sort_by_key(|(_, _, boost)| std::cmp::Reverse(boost.multiplier));
+
+        // Fetch mints and metadata
+        let mints: Vec<Pubkey> = boosts.iter().map(|(_, mint, _)| *mint).collect();
+        let metadata: Vec<Pubkey> = mints
+            .iter()
+            .map(|mint| Metadata::find_pda(mint).0)
+            .collect();
+        let mint_accounts = self.get_multiple_accounts_chunked(&mints).await?;
+        let metadata_accounts = self.get_multiple_accounts_chunked(&metadata).await?;
+
+        Ok(boosts
+            .into_iter()
+            .zip(mint_accounts.into_iter().zip(metadata_accounts))
+            .map(
+                |((address, mint, boost), (mint_account, metadata_account))| {
+                    let decimals = mint_account
+                        .and_then(|account| {
+                            StateWithExtensions::<Mint>::unpack(&account.data)
+                                .ok()
+                                .map(|mint| mint.base.decimals)
+                        })
+                        .unwrap_or(0);
+                    let symbol = metadata_account
+                        .and_then(|account| Metadata::from_bytes(&account.data).ok())
+                        .map(|metadata| metadata.symbol.trim_end_matches('\0').trim().to_string())
+                        .filter(|symbol| !symbol.is_empty());
+                    BoostInfo {
+                        address: address.to_string(),
+                        mint: mint.to_string(),
+                        symbol,
+                        decimals,
+                        multiplier: boost.multiplier,
+                        total_stake: boost.total_stake,
+                        expires_at: boost.expires_at,
+                    }
+                },
+            )
+            .collect())
+    }
+
+    /// The stake `authority` holds in `boosts`, both directly and as pool shares.
+    pub async fn get_stakes(
+        &self,
+        authority: Pubkey,
+        boosts: &[BoostInfo],
+    ) -> Result<Vec<StakePosition>, Error> {
+        let boosts_by_address: HashMap<String, &BoostInfo> = boosts
+            .iter()
+            .map(|boost| (boost.address.clone(), boost))
+            .collect();
+        let boosts_by_mint: HashMap<String, &BoostInfo> = boosts
+            .iter()
+            .map(|boost| (boost.mint.clone(), boost))
+            .collect();
+        let share_of = |balance: u64, boost: &BoostInfo| {
+            if boost.total_stake == 0 {
+                0f64
+            } else {
+                balance as f64 / boost.total_stake as f64
+            }
+        };
+
+        // Fetch solo stake
+        let mut positions = vec![];
+        let stakes = self
+            .get_program_accounts::<Stake>(ore_boost_api::ID, Some(authority))
+            .await?;
+        for (address, stake) in stakes {
+            let Some(boost) = boosts_by_address.get(&stake.boost.to_string()) else {
+                continue;
+            };
+            positions.push(StakePosition {
+                mint: boost.mint.clone(),
+                symbol: boost.symbol.clone(),
+                address: address.to_string(),
+                balance: stake.balance,
+                share: share_of(stake.balance, boost),
+                pool: None,
+            });
+        }
+
+        // Fetch pool shares
+        let shares = self
+            .get_program_accounts::<Share>(ore_pool_api::ID, Some(authority))
+            .await?;
+        for (address, share) in shares {
+            let Some(boost) = boosts_by_mint.get(&share.mint.to_string()) else {
+                continue;
+            };
+            positions.push(StakePosition {
+                mint: boost.mint.clone(),
+                symbol: boost.symbol.clone(),
+                address: address.to_string(),
+                balance: share.balance,
+                share: share_of(share.balance, boost),
+                pool: Some(share.pool.to_string()),
+            });
+        }
+        Ok(positions)
+    }
+
+    /// The mints of the unexpired boosts `authority` has staked in with the highest
+    /// effective multiplier, at most `count` of them.
+    pub async fn select_boosts(
+        &self,
+        authority: Pubkey,
+        count: usize,
+    ) -> Result<Vec<Pubkey>, Error> {
+        let boosts = self.get_boosts().await?;
+        let stakes = self.get_stakes(authority, &boosts).await?;
+        let now = chrono::Utc::now().timestamp();
+        Ok(rank_boosts(&boosts, &stakes, now, count))
+    }
+
+    /// All accounts of type `T` owned by `program_id`, optionally only those whose
+    /// first field is `authority`.
+    async fn get_program_accounts<T>(
+        &self,
+        program_id: Pubkey,
+        authority: Option<Pubkey>,
+    ) -> Result<Vec<(Pubkey, T)>, Error>
+    where
+        T: AccountDeserialize + Discriminator + Copy,
+    {
+        let mut filters = vec![
+            RpcFilterType::DataSize((8 + std::mem::size_of::<T>()) as u64),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, vec![T::discriminator()])),
+        ];
+        if let Some(authority) = authority {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                8,
+                authority.to_bytes().to_vec(),
+            )));
+        }
+        let accounts = self
+            .rpc_client
+            .get_program_accounts_with_config(
+                &program_id,
+                RpcProgramAccountsConfig {
+                    filters: Some(filters),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        Ok(accounts
+            .into_iter()
+            .filter_map(|(address, account)| {
+                let value = T::try_from_bytes(&account.data).ok()?;
+                Some((address, *value))
+            })
+            .collect())
+    }
+
+    /// Fetches accounts in batches the RPC accepts.
+    pub async fn get_multiple_accounts_chunked(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, Error> {
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(100) {
+            accounts.extend(self.rpc_client.get_multiple_accounts(chunk).await?);
+        }
+        Ok(accounts)
+    }
+}
+
+fn log_boost_list(list: &BoostList) {
+    let now = chrono::Utc::now().timestamp();
+    println!("{}", "Boosts".bold());
+    for boost in list.boosts.iter() {
+        let expiry = if boost.expires_at <= now {
+            "expired".red().to_string()
+        } else {
+            chrono::DateTime::from_timestamp(boost.expires_at, 0)
+                .map_or(boost.expires_at.to_string(), |at| at.to_rfc3339())
+        };
+        println!(
+            "  {} {}: {}x, {} staked, expires {}",
+            boost.mint,
+            boost.symbol.as_deref().unwrap_or("?"),
+            boost.multiplier,
+            amount_to_ui_amount(boost.total_stake, boost.decimals),
+            expiry
+        );
+    }
+    println!("\n{} ({})", "Stakes".bold(), list.authority);
+    if list.stakes.is_empty() {
+        println!("  None");
+    }
+    for stake in list.stakes.iter() {
+        let decimals = list
+            .boosts
+            .iter()
+            .find(|boost| boost.mint == stake.mint)
+            .map_or(0, |boost| boost.decimals);
+        println!(
+            "  {} {}: {} ({:.4}% of boost){}",
+            stake.mint,
+            stake.symbol.as_deref().unwrap_or("?"),
+            amount_to_ui_amount(stake.balance, decimals),
+            stake.share * 100f64,
+            stake
+                .pool
+                .as_ref()
+                .map_or("".to_string(), |pool| format!(" via pool {}", pool))
+        );
+    }
+}
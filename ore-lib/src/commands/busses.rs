@@ -1,23 +1,51 @@
-use ore_api::{
-    consts::{BUS_ADDRESSES, TOKEN_DECIMALS},
-    state::Bus,
-};
+use ore_api::{consts::BUS_ADDRESSES, state::Bus};
+use serde::Serialize;
 use steel::AccountDeserialize;
 
-use crate::Miner;
+use crate::{error::Error, output, utils::amount_u64_to_f64, Miner};
+
+/// A bus account, with amounts in grains.
+#[derive(Clone, Debug, Serialize)]
+pub struct BusInfo {
+    pub id: u64,
+    pub address: String,
+    /// What the bus has left to pay out this epoch.
+    pub rewards: u64,
+    pub theoretical_rewards: u64,
+    pub top_balance: u64,
+}
 
 impl Miner {
     pub async fn busses(&self) {
-        let client = self.rpc_client.clone();
-        for address in BUS_ADDRESSES.iter() {
-            let data = client.get_account_data(address).await.unwrap();
-            match Bus::try_from_bytes(&data) {
-                Ok(bus) => {
-                    let rewards = (bus.rewards as f64) / 10f64.powf(TOKEN_DECIMALS as f64);
-                    println!("Bus {}: {:} ORE", bus.id, rewards);
+        match self.get_busses().await {
+            Ok(busses) => output::print(&busses, |busses| {
+                for bus in busses {
+                    println!("Bus {}: {:} ORE", bus.id, amount_u64_to_f64(bus.rewards));
                 }
-                Err(_) => {}
-            }
+            }),
+            Err(err) => output::print_error(err),
         }
     }
+
+    /// All bus accounts, skipping any that fail to load.
+    pub async fn get_busses(&self) -> Result<Vec<BusInfo>, Error> {
+        let accounts = self
+            .rpc_client
+            .get_multiple_accounts(&BUS_ADDRESSES)
+            .await?;
+        Ok(accounts
+            .iter()
+            .zip(BUS_ADDRESSES.iter())
+            .filter_map(|(account, address)| {
+                let bus = Bus::try_from_bytes(&account.as_ref()?.data).ok()?;
+                Some(BusInfo {
+                    id: bus.id,
+                    address: address.to_string(),
+                    rewards: bus.rewards,
+                    theoretical_rewards: bus.theoretical_rewards,
+                    top_balance: bus.top_balance,
+                })
+            })
+            .collect())
+    }
 }
@@ -1,71 +1,68 @@
 use std::str::FromStr;
 
 use colored::*;
-use ore_api::consts::MINT_ADDRESS;
+use ore_api::{consts::MINT_ADDRESS, state::Proof};
+use serde::Serialize;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::signature::Signer;
 use spl_token::amount_to_ui_amount;
+use steel::AccountDeserialize;
 
 use crate::{
     args::ClaimArgs,
-    cu_limits::CU_LIMIT_CLAIM,
+    cu_limits::{CU_LIMIT_CLAIM, CU_LIMIT_MEMO},
+    miner::Miner,
+    output::{self, TxOutcome},
     pool::Pool,
     send_and_confirm::ComputeBudget,
-    utils::{amount_f64_to_u64, ask_confirm, get_proof_with_authority},
-    miner::Miner,
+    token_account::{check_memo, memo_ix},
+    utils::{amount_f64_to_u64, proof_pubkey},
 };
 
+/// The outcome of a claim.
+#[derive(Clone, Debug, Serialize)]
+pub struct ClaimOutput {
+    /// In grains.
+    pub amount: u64,
+    pub beneficiary: String,
+    #[serde(flatten)]
+    pub tx: TxOutcome,
+}
+
 impl Miner {
     pub async fn claim(&self, args: ClaimArgs) -> Result<(), crate::error::Error> {
-        match args.pool_url {
+        let claim = self.claim_rewards(args).await?;
+        output::print(&claim, |_| {});
+        Ok(())
+    }
+
+    /// Claims from the signer's proof or, given a pool url, its pool balance, after
+    /// asking the confirmer.
+    pub async fn claim_rewards(&self, args: ClaimArgs) -> Result<ClaimOutput, crate::error::Error> {
+        let claim = match args.pool_url {
             Some(ref pool_url) => {
                 let pool = &Pool {
                     http_client: reqwest::Client::new(),
                     pool_url: pool_url.clone(),
                 };
-                let _ = self.claim_from_pool(args, pool).await?;
-                Ok(())
-            }
-            None => {
-                self.claim_from_proof(args).await;
-                Ok(())
+                self.claim_from_pool(args, pool).await?
             }
-        }
+            None => self.claim_from_proof(args).await?,
+        };
+        Ok(claim)
     }
 
-    pub async fn claim_from_proof(&self, args: ClaimArgs) {
+    pub async fn claim_from_proof(
+        &self,
+        args: ClaimArgs,
+    ) -> Result<ClaimOutput, crate::error::Error> {
         let signer = self.signer();
         let pubkey = signer.pubkey();
-        let proof = get_proof_with_authority(&self.rpc_client, pubkey).await;
-        let mut ixs = vec![];
-        let beneficiary = match args.to {
-            None => self.initialize_ata(pubkey).await,
-            Some(to) => {
-                // Create beneficiary token account, if needed
-                let wallet = Pubkey::from_str(&to).expect("Failed to parse wallet address");
-                let benefiary_tokens = spl_associated_token_account::get_associated_token_address(
-                    &wallet,
-                    &MINT_ADDRESS,
-                );
-                if self
-                    .rpc_client
-                    .get_token_account(&benefiary_tokens)
-                    .await
-                    .is_err()
-                {
-                    ixs.push(
-                        spl_associated_token_account::instruction::create_associated_token_account(
-                            &pubkey,
-                            &wallet,
-                            &ore_api::consts::MINT_ADDRESS,
-                            &spl_token::id(),
-                        ),
-                    );
-                }
-                benefiary_tokens
-            }
-        };
-
+        let data = self
+            .rpc_client
+            .get_account_data(&proof_pubkey(pubkey))
+            .await?;
+        let proof = Proof::try_from_bytes(&data)?;
         // Parse amount to claim
         let amount = if let Some(amount) = args.amount {
             amount_f64_to_u64(amount)
@@ -73,8 +70,20 @@ impl Miner {
             proof.balance
         };
 
+        // Create beneficiary token account, if needed
+        let wallet = match args.to {
+            Some(ref to) => Pubkey::from_str(to)?,
+            None => pubkey,
+        };
+        let ata = self.get_ata(MINT_ADDRESS, &wallet).await?;
+        let beneficiary = ata.address;
+        let mut ixs: Vec<_> = ata.create_ix.clone().into_iter().collect();
+
+        // Check the beneficiary doesn't require a memo we lack
+        check_memo(&self.rpc_client, &beneficiary, args.memo.as_deref()).await?;
+
         // Confirm user wants to claim
-        if !ask_confirm(
+        if !self.confirmer.confirm(
             format!(
                 "\nYou are about to claim {}.\n\nAre you sure you want to continue? [Y/n]",
                 format!(
@@ -85,54 +94,39 @@ impl Miner {
             )
             .as_str(),
         ) {
-            return;
+            return Ok(ClaimOutput {
+                amount,
+                beneficiary: beneficiary.to_string(),
+                tx: TxOutcome::cancelled(),
+            });
         }
 
         // Send and confirm
+        let mut compute_units = CU_LIMIT_CLAIM + ata.compute_units();
+        if let Some(memo) = &args.memo {
+            ixs.push(memo_ix(memo, &pubkey));
+            compute_units += CU_LIMIT_MEMO;
+        }
         ixs.push(ore_api::sdk::claim(pubkey, beneficiary, amount));
-        self.send_and_confirm(&ixs, ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
-            .await
-            .ok();
+        let result = self
+            .send_and_confirm(&ixs, ComputeBudget::Fixed(compute_units), false)
+            .await;
+        Ok(ClaimOutput {
+            amount,
+            beneficiary: beneficiary.to_string(),
+            tx: TxOutcome::new(&result),
+        })
     }
 
     async fn claim_from_pool(
         &self,
         args: ClaimArgs,
         pool: &Pool,
-    ) -> Result<Signature, crate::error::Error> {
+    ) -> Result<ClaimOutput, crate::error::Error> {
         let pool_address = pool.get_pool_address().await?;
         let member = pool
             .get_pool_member_onchain(self, pool_address.address)
             .await?;
-        let mut ixs = vec![];
-        let beneficiary = match args.to {
-            None => self.initialize_ata(self.signer().pubkey()).await,
-            Some(to) => {
-                // Create beneficiary token account, if needed
-                let wallet = Pubkey::from_str(&to).expect("Failed to parse wallet address");
-                let benefiary_tokens = spl_associated_token_account::get_associated_token_address(
-                    &wallet,
-                    &MINT_ADDRESS,
-                );
-                if self
-                    .rpc_client
-                    .get_token_account(&benefiary_tokens)
-                    .await
-                    .is_err()
-                {
-                    ixs.push(
-                        spl_associated_token_account::instruction::create_associated_token_account(
-                            &self.signer().pubkey(),
-                            &wallet,
-                            &ore_api::consts::MINT_ADDRESS,
-                            &spl_token::id(),
-                        ),
-                    );
-                }
-                benefiary_tokens
-            }
-        };
-
         // Parse amount to claim
         let amount = if let Some(amount) = args.amount {
             amount_f64_to_u64(amount)
@@ -140,8 +134,20 @@ impl Miner {
             member.balance
         };
 
+        // Create beneficiary token account, if needed
+        let wallet = match args.to {
+            Some(ref to) => Pubkey::from_str(to)?,
+            None => self.signer().pubkey(),
+        };
+        let ata = self.get_ata(MINT_ADDRESS, &wallet).await?;
+        let beneficiary = ata.address;
+        let mut ixs: Vec<_> = ata.create_ix.clone().into_iter().collect();
+
+        // Check the beneficiary doesn't require a memo we lack
+        check_memo(&self.rpc_client, &beneficiary, args.memo.as_deref()).await?;
+
         // Confirm user wants to claim
-        if !ask_confirm(
+        if !self.confirmer.confirm(
             format!(
                 "\nYou are about to claim {}.\n\nAre you sure you want to continue? [Y/n]",
                 format!(
@@ -152,10 +158,19 @@ impl Miner {
             )
             .as_str(),
         ) {
-            return Err(crate::error::Error::Internal("exited claim".to_string()));
+            return Ok(ClaimOutput {
+                amount,
+                beneficiary: beneficiary.to_string(),
+                tx: TxOutcome::cancelled(),
+            });
         }
 
         // Send and confirm
+        let mut compute_units = 50_000 + ata.compute_units();
+        if let Some(memo) = &args.memo {
+            ixs.push(memo_ix(memo, &self.signer().pubkey()));
+            compute_units += CU_LIMIT_MEMO;
+        }
         ixs.push(ore_pool_api::sdk::claim(
             self.signer().pubkey(),
             beneficiary,
@@ -163,38 +178,13 @@ impl Miner {
             pool_address.bump,
             amount,
         ));
-        self.send_and_confirm(&ixs, ComputeBudget::Fixed(50_000), false)
-            .await
-            .map_err(From::from)
-    }
-
-    async fn initialize_ata(&self, wallet: Pubkey) -> Pubkey {
-        // Initialize client.
-        let signer = self.signer();
-        let client = self.rpc_client.clone();
-
-        // Build instructions.
-        let token_account_pubkey = spl_associated_token_account::get_associated_token_address(
-            &wallet,
-            &ore_api::consts::MINT_ADDRESS,
-        );
-
-        // Check if ata already exists
-        if let Ok(Some(_ata)) = client.get_token_account(&token_account_pubkey).await {
-            return token_account_pubkey;
-        }
-        // Sign and send transaction.
-        let ix = spl_associated_token_account::instruction::create_associated_token_account(
-            &signer.pubkey(),
-            &signer.pubkey(),
-            &ore_api::consts::MINT_ADDRESS,
-            &spl_token::id(),
-        );
-        self.send_and_confirm(&[ix], ComputeBudget::Fixed(400_000), false)
-            .await
-            .ok();
-
-        // Return token account address
-        token_account_pubkey
+        let result = self
+            .send_and_confirm(&ixs, ComputeBudget::Fixed(compute_units), false)
+            .await;
+        Ok(ClaimOutput {
+            amount,
+            beneficiary: beneficiary.to_string(),
+            tx: TxOutcome::new(&result),
+        })
     }
 }
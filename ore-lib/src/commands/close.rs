@@ -1,45 +1,81 @@
 use colored::*;
+use serde::Serialize;
 use solana_sdk::signature::Signer;
 use spl_token::amount_to_ui_amount;
 
 use crate::{
     args::ClaimArgs,
+    commands::claim::ClaimOutput,
+    output::{self, TxOutcome},
     send_and_confirm::ComputeBudget,
-    utils::{ask_confirm, get_proof_with_authority},
+    utils::{get_proof_with_authority},
     Miner,
 };
 
+/// The outcome of closing a proof, claiming its stake first if any.
+#[derive(Clone, Debug, Serialize)]
+pub struct CloseOutput {
+    pub claim: Option<ClaimOutput>,
+    #[serde(flatten)]
+    pub tx: TxOutcome,
+}
+
 impl Miner {
     pub async fn close(&self) {
+        let close = self.close_proof().await;
+        output::print(&close, |_| {});
+    }
+
+    async fn close_proof(&self) -> CloseOutput {
         // Confirm proof exists
         let signer = self.signer();
         let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await;
 
         // Confirm the user wants to close.
-        if !ask_confirm(
+        if !self.confirmer.confirm(
             format!("{} You have {} ORE staked in this account.\nAre you sure you want to {}close this account? [Y/n]", 
                 "WARNING".yellow(),
                 amount_to_ui_amount(proof.balance, ore_api::consts::TOKEN_DECIMALS),
                 if proof.balance.gt(&0) { "claim your stake and "} else { "" }
             ).as_str()
         ) {
-            return;
+            return CloseOutput {
+                claim: None,
+                tx: TxOutcome::cancelled(),
+            };
         }
 
         // Claim stake
-        if proof.balance.gt(&0) {
-            self.claim_from_proof(ClaimArgs {
-                amount: None,
-                to: None,
-                pool_url: None,
-            })
-            .await;
-        }
+        let claim = if proof.balance.gt(&0) {
+            let claim = self
+                .claim_from_proof(ClaimArgs {
+                    amount: None,
+                    to: None,
+                    pool_url: None,
+                    memo: None,
+                })
+                .await;
+            match claim {
+                Ok(claim) => Some(claim),
+                Err(err) => {
+                    return CloseOutput {
+                        claim: None,
+                        tx: TxOutcome::failed(format!("{:?}", err)),
+                    }
+                }
+            }
+        } else {
+            None
+        };
 
         // Submit close transaction
         let ix = ore_api::sdk::close(signer.pubkey());
-        self.send_and_confirm(&[ix], ComputeBudget::Fixed(500_000), false)
-            .await
-            .ok();
+        let result = self
+            .send_and_confirm(&[ix], ComputeBudget::Fixed(500_000), false)
+            .await;
+        CloseOutput {
+            claim,
+            tx: TxOutcome::new(&result),
+        }
     }
 }
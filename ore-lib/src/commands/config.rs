@@ -1,22 +1,51 @@
 use colored::Colorize;
-use ore_api::consts::EPOCH_DURATION;
-
-use crate::{
-    utils::{amount_u64_to_string, get_config},
-    Miner,
+use ore_api::{
+    consts::{CONFIG_ADDRESS, EPOCH_DURATION},
+    state::Config,
 };
+use serde::Serialize;
+use steel::AccountDeserialize;
+
+use crate::{error::Error, output, utils::amount_u64_to_string, Miner};
+
+/// The program config, with amounts in grains.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConfigInfo {
+    pub last_reset_at: i64,
+    pub min_difficulty: u64,
+    pub base_reward_rate: u64,
+    pub top_balance: u64,
+    /// In seconds.
+    pub epoch_duration: i64,
+}
 
 impl Miner {
     pub async fn config(&self) {
-        let config = get_config(&self.rpc_client).await;
-        println!("{}: {}", "Last reset at".bold(), config.last_reset_at);
-        println!("{}: {}", "Min difficulty".bold(), config.min_difficulty);
-        println!("{}: {}", "Base reward rate".bold(), config.base_reward_rate);
-        println!(
-            "{}: {} ORE",
-            "Top stake".bold(),
-            amount_u64_to_string(config.top_balance)
-        );
-        println!("{}: {} sec", "Epoch time".bold(), EPOCH_DURATION);
+        match self.get_config_info().await {
+            Ok(config) => output::print(&config, |config| {
+                println!("{}: {}", "Last reset at".bold(), config.last_reset_at);
+                println!("{}: {}", "Min difficulty".bold(), config.min_difficulty);
+                println!("{}: {}", "Base reward rate".bold(), config.base_reward_rate);
+                println!(
+                    "{}: {} ORE",
+                    "Top stake".bold(),
+                    amount_u64_to_string(config.top_balance)
+                );
+                println!("{}: {} sec", "Epoch time".bold(), config.epoch_duration);
+            }),
+            Err(err) => output::print_error(err),
+        }
+    }
+
+    pub async fn get_config_info(&self) -> Result<ConfigInfo, Error> {
+        let data = self.rpc_client.get_account_data(&CONFIG_ADDRESS).await?;
+        let config = Config::try_from_bytes(&data)?;
+        Ok(ConfigInfo {
+            last_reset_at: config.last_reset_at,
+            min_difficulty: config.min_difficulty,
+            base_reward_rate: config.base_reward_rate,
+            top_balance: config.top_balance,
+            epoch_duration: EPOCH_DURATION,
+        })
     }
 }
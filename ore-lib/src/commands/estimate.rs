@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use colored::*;
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    args::EstimateArgs,
+    core_alloc::CoreAllocation,
+    cu_limits::CU_LIMIT_MINE_TX,
+    error::Error,
+    hash_backend::CpuBackend,
+    output::{self, status},
+    profitability::Estimate,
+    throttle::Throttle,
+    utils::get_config,
+    Miner,
+};
+
+impl Miner {
+    pub async fn estimate(&self, args: EstimateArgs) {
+        match self.get_estimate(&args).await {
+            Ok(estimate) => output::print(&estimate, |estimate| {
+                println!(
+                    "Hashrate: {} H/sec ({} hashes per round)",
+                    estimate.hashrate,
+                    (estimate.hashrate * estimate.round_time as f64) as u64
+                );
+                println!("Best difficulty per round:");
+                for (difficulty, p) in estimate.distribution.iter() {
+                    if p.ge(&0.001) {
+                        let line = format!("  {}: {:.1}%", difficulty, p * 100.0);
+                        if difficulty.lt(&estimate.min_difficulty) {
+                            println!("{}", line.dimmed());
+                        } else {
+                            println!("{}", line);
+                        }
+                    }
+                }
+                println!(
+                    "Below min difficulty ({}): {:.1}%",
+                    estimate.min_difficulty,
+                    estimate.below_min * 100.0
+                );
+                println!("Expected reward: {:.11} ORE/hour", estimate.ore_per_hour);
+                println!("Expected cost: {:.9} SOL/hour", estimate.cost_sol_per_hour);
+                if let Some(net) = estimate.net_sol_per_hour() {
+                    println!("Expected profit: {:.9} SOL/hour", net);
+                }
+            }),
+            Err(err) => output::print_error(err),
+        }
+    }
+
+    pub async fn get_estimate(&self, args: &EstimateArgs) -> Result<Estimate, Error> {
+        // Measure the hashrate, unless given
+        let hashrate = match args.hashrate {
+            Some(hashrate) => hashrate,
+            None => {
+                self.check_num_cores(args.cores);
+                status(format!("Measuring hashrate for {} sec...", args.duration));
+                self.run_benchmark(
+                    CpuBackend::new(CoreAllocation::default(), Throttle::default()),
+                    args.cores,
+                    args.duration,
+                    [0; 32],
+                )
+                .hashrate as f64
+            }
+        };
+
+        // Fetch boost shares
+        let mut boosts = vec![];
+        for mint in args.boosts.iter() {
+            match self.get_boost_share(Pubkey::from_str(mint)?).await {
+                Some(boost) => boosts.push(boost),
+                None => status(format!(
+                    "{} No stake found for boost {}",
+                    "WARNING".bold().yellow(),
+                    mint
+                )),
+            }
+        }
+
+        let config = get_config(&self.rpc_client).await;
+        let cost = self.estimate_tx_cost(CU_LIMIT_MINE_TX).await;
+        Ok(Estimate::new(
+            &config,
+            hashrate,
+            args.round_time,
+            &boosts,
+            &cost,
+            args.ore_price_sol,
+        ))
+    }
+}
@@ -1,87 +1,120 @@
 use std::{
-    str::FromStr,
-    sync::{Arc, RwLock},
-    time::Instant,
-    usize,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use colored::*;
-use drillx::{
-    equix::{self},
-    Hash, Solution,
-};
+use drillx::Solution;
 use mpl_token_metadata::accounts::Metadata;
 use ore_api::{
-    consts::{BUS_ADDRESSES, BUS_COUNT, EPOCH_DURATION},
+    consts::{BUS_ADDRESSES, EPOCH_DURATION, TOLERANCE},
     state::{Bus, Config},
 };
-use ore_boost_api::state::{boost_pda, stake_pda};
+use ore_boost_api::state::{boost_pda, stake_pda, Boost, Stake};
 use rand::Rng;
-use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
 use solana_rpc_client::{nonblocking::rpc_client::RpcClient, spinner};
 use solana_sdk::signer::Signer;
-use spl_token::state::Mint;
+use spl_token_2022::{extension::StateWithExtensions, state::Mint};
 use steel::AccountDeserialize;
-use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::{
-    args::MineArgs, error::Error, pool::Pool, send_and_confirm::ComputeBudget, utils::{
-        amount_u64_to_string, get_boost, get_clock, get_config, get_stake,
-        get_updated_proof_with_authority, proof_pubkey,
-    }, Miner
+    args::MineArgs,
+    auto_claim::{AutoClaimPolicy, AutoClaimer},
+    boost_select::{BoostMode, BoostSelector, MAX_BOOSTS},
+    bus::{BusChoice, BusSelector},
+    control::MiningControl,
+    core_alloc::CoreAllocation,
+    cu_limits::CU_LIMIT_MINE_TX,
+    error::Error,
+    hash_backend::{CpuBackend, HashBackend, HashJob, HashProgress, StopPolicy},
+    journal::Journal,
+    nonce::NonceAllocator,
+    output::{self, status, TxOutcome, TxStatus},
+    pool::Pool,
+    profitability::{BoostShare, Profitability, TxCost, UnprofitablePolicy},
+    remote::ClusterBackend,
+    send_and_confirm::ComputeBudget,
+    throttle::Throttle,
+    utils::{
+        amount_u64_to_string, get_clock, get_config, get_proof_with_authority,
+        get_updated_proof_with_authority, proof_pubkey, reward_for_difficulty,
+    },
+    Miner,
 };
 
+/// A solution found while mining. With `--output json`, each is printed as one JSON line.
+#[derive(Clone, Debug, Serialize)]
+pub struct MineSubmission {
+    pub difficulty: u32,
+    /// The bus the solution was submitted to, when mining solo. Pool solutions
+    /// are sent to the pool operator, with no signature of their own.
+    pub bus: Option<usize>,
+    #[serde(flatten)]
+    pub tx: TxOutcome,
+}
+
 impl Miner {
-    pub async fn mine(&self, args: MineArgs, is_mining: &Arc<AtomicBool>) -> Result<(), Error> {
-        println!("ore-lib: Let's go to the mines!");
+    pub async fn mine(&self, args: MineArgs, control: &MiningControl) -> Result<(), Error> {
+        status("ore-lib: Let's go to the mines!");
 
         match args.pool_url {
             Some(ref pool_url) => {
-                println!("ore-lib: Mining pool");
+                status("ore-lib: Mining pool");
                 let pool = &Pool {
                     http_client: reqwest::Client::new(),
                     pool_url: pool_url.clone(),
                 };
-                self.mine_pool(args, pool, is_mining).await?;
+                self.mine_pool(args, pool, control).await?;
             }
             None => {
-                println!("ore-lib: Mining solo");
-                self.mine_solo(args, is_mining).await?;
+                status("ore-lib: Mining solo");
+                self.mine_solo(args, control).await?;
             }
         }
         Ok(())
     }
 
-    async fn mine_solo(&self, args: MineArgs, is_mining: &Arc<AtomicBool>) -> Result<(), Error> {
-        // Open account, if needed.
+    async fn mine_solo(&self, args: MineArgs, control: &MiningControl) -> Result<(), Error> {
+        // Fetch boost data
         let signer = self.signer();
+        let mut boost_data =
+            fetch_boost_data(self.rpc_client.clone(), signer.pubkey(), &args.boosts).await?;
+        let mut boost_selector = BoostSelector::new(args.boost_refresh);
+        let mut auto_claimer = AutoClaimer::new(
+            AutoClaimPolicy::from_args(&args)?,
+            args.journal.clone().map(Journal::new),
+        );
+
+        // Open account, if needed.
         self.open().await;
 
         // Check num threads
         self.check_num_cores(args.cores);
-
-        // Fetch boost data
-        let boost_data_1 =
-            fetch_boost_data(self.rpc_client.clone(), signer.pubkey(), &args.boost_1).await;
-        let boost_data_2 =
-            fetch_boost_data(self.rpc_client.clone(), signer.pubkey(), &args.boost_2).await;
-        let boost_data_3 =
-            fetch_boost_data(self.rpc_client.clone(), signer.pubkey(), &args.boost_3).await;
+        let backend = self.mining_backend(&args);
 
         // Start mining loop
+        let allocator = NonceAllocator::solo(args.randomize_nonce);
+        let mut bus_selector = BusSelector::new(args.bus_strategy);
         let mut last_hash_at = 0;
         let mut last_balance = 0;
-        println!("is_mining: {}", is_mining.load(Ordering::SeqCst));
-        while is_mining.load(Ordering::SeqCst) {
-            println!("Mining solo loop");
-            // Fetch proof
+        let mut retry = false;
+        status(format!("is_mining: {}", control.is_mining()));
+        while control.is_mining() {
+            status("Mining solo loop");
+            // Fetch proof. A skipped solution leaves the challenge as it was.
+            let retrying = std::mem::take(&mut retry);
             let config = get_config(&self.rpc_client).await;
-            let proof =
+            let proof = if retrying {
+                get_proof_with_authority(&self.rpc_client, signer.pubkey()).await
+            } else {
                 get_updated_proof_with_authority(&self.rpc_client, signer.pubkey(), last_hash_at)
-                    .await;
+                    .await
+            };
 
             // Print unclaimed balance
-            println!(
+            status(format!(
                 "\n\nBalance: {} ORE{}",
                 amount_u64_to_string(proof.balance),
                 if last_hash_at.gt(&0) {
@@ -92,39 +125,109 @@ impl Miner {
                 } else {
                     "".to_string()
                 },
-            );
+            ));
+
+            // Claim rewards, if due
+            auto_claimer.check(self, proof.balance, None).await;
+
+            // Re-evaluate automatic boosts
+            if args.boost == Some(BoostMode::Auto) && boost_selector.tick() {
+                let selected = match self.select_boosts(signer.pubkey(), MAX_BOOSTS).await {
+                    Ok(mints) => {
+                        fetch_boost_data(self.rpc_client.clone(), signer.pubkey(), &mints).await
+                    }
+                    Err(err) => Err(err),
+                };
+                match selected {
+                    Ok(selected) => boost_data = selected,
+                    Err(err) => status(format!(
+                        "{} Failed to select boosts, keeping the current ones: {:?}",
+                        "WARNING".bold().yellow(),
+                        err
+                    )),
+                }
+            }
 
             // Print boosts
-            log_boost_data(self.rpc_client.clone(), &boost_data_1, 1).await;
-            log_boost_data(self.rpc_client.clone(), &boost_data_2, 2).await;
-            log_boost_data(self.rpc_client.clone(), &boost_data_3, 3).await;
+            let mut boosts: Vec<BoostShare> = vec![];
+            for (i, boost_data) in boost_data.iter().enumerate() {
+                boosts.extend(log_boost_data(self.rpc_client.clone(), boost_data, i + 1).await);
+            }
             last_hash_at = proof.last_hash_at;
             last_balance = proof.balance;
 
             // Calculate cutoff time
             let cutoff_time = self.get_cutoff(proof.last_hash_at, args.buffer_time).await;
+            let mut policy =
+                StopPolicy::new(&args, Some(&config), cutoff_time, config.min_difficulty as u32);
 
-            // Build nonce indices
-            let mut nonce_indices = Vec::with_capacity(args.cores as usize);
-            for n in 0..(args.cores) {
-                let nonce = u64::MAX.saturating_div(args.cores).saturating_mul(n);
-                nonce_indices.push(nonce);
+            // Keep hashing past the cutoff until the reward would cover the fees
+            let cost = match args.ore_price_sol {
+                Some(_) => self.estimate_tx_cost(CU_LIMIT_MINE_TX).await,
+                None => TxCost::default(),
+            };
+            if let Some(ore_price_sol) = args.ore_price_sol {
+                let break_even =
+                    Profitability::break_even_difficulty(&config, &boosts, &cost, ore_price_sol);
+                match (args.unprofitable, break_even) {
+                    (UnprofitablePolicy::Submit, _) => {}
+                    (_, None) => status(format!(
+                        "{} No difficulty breaks even at {} SOL/ORE",
+                        "WARNING".bold().yellow(),
+                        ore_price_sol
+                    )),
+                    (UnprofitablePolicy::Delay | UnprofitablePolicy::Skip, Some(floor)) => {
+                        policy = policy.with_floor(floor, Some(args.profit_delay))
+                    }
+                }
             }
 
+            // Build nonce ranges
+            let cores = control.cores().unwrap_or(args.cores);
+
             // Run drillx
-            let solution = Self::find_hash_par(
-                proof.challenge,
-                cutoff_time,
-                args.cores,
-                config.min_difficulty as u32,
-                nonce_indices.as_slice(),
-                &is_mining,
-            )
-            .await;
+            let job = HashJob {
+                challenge: proof.challenge,
+                nonce_ranges: if retrying {
+                    allocator.retry_ranges(cores)
+                } else {
+                    allocator.thread_ranges(cores)
+                },
+                policy,
+            };
+            let solution = self.find_hash_par(backend.clone(), job, control).await?;
+
+            // Honor a pending stop request before submitting
+            let difficulty = solution.to_hash().difficulty();
+            if !control.should_submit(difficulty, config.min_difficulty as u32) {
+                break;
+            }
+
+            // Weigh the expected reward against the fees
+            if let Some(ore_price_sol) = args.ore_price_sol {
+                let estimate =
+                    Profitability::new(&config, difficulty, &boosts, &cost, ore_price_sol);
+                status(format!(
+                    "  Expected reward: {} ORE ({:.9} SOL), cost {:.9} SOL",
+                    amount_u64_to_string(estimate.reward),
+                    estimate.reward_sol(),
+                    estimate.cost_sol()
+                ));
+
+                // Skipping hashes the same challenge again in the next round
+                if !estimate.is_profitable() && args.unprofitable.eq(&UnprofitablePolicy::Skip) {
+                    status("Skipping unprofitable solution");
+                    retry = true;
+                    continue;
+                }
+            }
+
+            // Hold early solutions until the program stops treating them as spam
+            self.wait_for_submission_window(proof.last_hash_at).await;
 
             // Build instruction set
             let mut ixs = vec![ore_api::sdk::auth(proof_pubkey(signer.pubkey()))];
-            let mut compute_budget = 600_000;
+            let mut compute_budget = CU_LIMIT_MINE_TX;
 
             // Check for reset
             if self.should_reset(config).await && rand::thread_rng().gen_range(0..100).eq(&0) {
@@ -133,26 +236,41 @@ impl Miner {
             }
 
             // Build option (boost) accounts
-            let mut optional_accounts: Vec<Pubkey> = vec![];
-            optional_accounts = [optional_accounts, BoostData::to_vec(&boost_data_1)].concat();
-            optional_accounts = [optional_accounts, BoostData::to_vec(&boost_data_2)].concat();
-            optional_accounts = [optional_accounts, BoostData::to_vec(&boost_data_3)].concat();
+            let optional_accounts: Vec<Pubkey> =
+                boost_data.iter().flat_map(BoostData::accounts).collect();
+            // Pick a bus
+            let expected_reward = reward_for_difficulty(&config, difficulty);
+            let bus = self.find_bus(&mut bus_selector, expected_reward).await;
+            status(format!(
+                "  Bus: #{} ({} ORE left, expected reward {} ORE)",
+                bus.id,
+                bus.rewards
+                    .map(amount_u64_to_string)
+                    .unwrap_or("unknown".to_string()),
+                amount_u64_to_string(expected_reward)
+            ));
+
             // Build mine ix
             let ix = ore_api::sdk::mine(
                 signer.pubkey(),
                 signer.pubkey(),
-                self.find_bus().await,
+                BUS_ADDRESSES[bus.id],
                 solution,
                 optional_accounts,
             );
             ixs.push(ix);
 
             // Submit transaction
-            self.send_and_confirm(&ixs, ComputeBudget::Fixed(compute_budget), false)
-                .await
-                .ok();
-
-            if !is_mining.load(Ordering::SeqCst) {
+            let result = self
+                .send_and_confirm(&ixs, ComputeBudget::Fixed(compute_budget), false)
+                .await;
+            output::event(&MineSubmission {
+                difficulty,
+                bus: Some(bus.id),
+                tx: TxOutcome::new(&result),
+            });
+
+            if !control.is_mining() {
                 break;
             }
         }
@@ -160,22 +278,27 @@ impl Miner {
         Ok(())
     }
 
-    async fn mine_pool(&self, args: MineArgs, pool: &Pool, is_mining: &Arc<AtomicBool>) -> Result<(), Error> {
+    async fn mine_pool(&self, args: MineArgs, pool: &Pool, control: &MiningControl) -> Result<(), Error> {
         // register, if needed
         let mut pool_member = pool.post_pool_register(self).await?;
-        let nonce_index = pool_member.id as u64;
+        let mut allocator = NonceAllocator::pool(pool_member.id as u64, args.randomize_nonce);
         // get on-chain pool accounts
         let pool_address = pool.get_pool_address().await?;
         let mut pool_member_onchain: ore_pool_api::state::Member;
-        println!("pool_address: {:?}", pool_address);
+        status(format!("pool_address: {:?}", pool_address));
         // Check num threads
         self.check_num_cores(args.cores);
+        let backend = self.mining_backend(&args);
+        let mut auto_claimer = AutoClaimer::new(
+            AutoClaimPolicy::from_args(&args)?,
+            args.journal.clone().map(Journal::new),
+        );
         // Start mining loop
         let mut last_hash_at = 0;
         let mut last_balance: i64;
-        println!("is_mining: {}", is_mining.load(Ordering::SeqCst));
-        while is_mining.load(Ordering::SeqCst) {
-            println!("Mining pool loop");
+        status(format!("is_mining: {}", control.is_mining()));
+        while control.is_mining() {
+            status("Mining pool loop");
             // Fetch latest challenge
             let member_challenge = match pool.get_updated_pool_challenge(last_hash_at).await {
                 Err(_err) => {
@@ -189,28 +312,49 @@ impl Miner {
             last_hash_at = member_challenge.challenge.lash_hash_at;
             // Compute cutoff time
             let cutoff_time = self.get_cutoff(last_hash_at, member_challenge.buffer).await;
-            // Build nonce indices
-            let num_total_members = member_challenge.num_total_members.max(1);
-            let u64_unit = u64::MAX.saturating_div(num_total_members);
-            let left_bound = u64_unit.saturating_mul(nonce_index);
-            let range_per_core = u64_unit.saturating_div(args.cores);
-            let mut nonce_indices = Vec::with_capacity(args.cores as usize);
-            for n in 0..(args.cores) {
-                let index = left_bound + n * range_per_core;
-                nonce_indices.push(index);
-            }
-            // Run drillx
-            let solution = Self::find_hash_par(
-                member_challenge.challenge.challenge,
+            let config = match args.target_reward {
+                Some(_) => Some(get_config(&self.rpc_client).await),
+                None => None,
+            };
+            let policy = StopPolicy::new(
+                &args,
+                config.as_ref(),
                 cutoff_time,
-                args.cores,
                 member_challenge.challenge.min_difficulty as u32,
-                nonce_indices.as_slice(),
-                &is_mining,
-            )
-            .await;
+            );
+            // Build nonce ranges
+            if allocator.set_members(member_challenge.num_total_members) {
+                status(format!("Pool members: {}", allocator.members()));
+            }
+            let cores = control.cores().unwrap_or(args.cores);
+            // Run drillx
+            let job = HashJob {
+                challenge: member_challenge.challenge.challenge,
+                nonce_ranges: allocator.thread_ranges(cores),
+                policy,
+            };
+            let solution = self.find_hash_par(backend.clone(), job, control).await?;
+            // Honor a pending stop request before posting
+            let difficulty = solution.to_hash().difficulty();
+            if !control.should_submit(difficulty, member_challenge.challenge.min_difficulty as u32)
+            {
+                break;
+            }
             // Post solution to operator
-            if let Err(_err) = pool.post_pool_solution(self, &solution).await {
+            let result = pool.post_pool_solution(self, &solution).await;
+            output::event(&MineSubmission {
+                difficulty,
+                bus: None,
+                tx: match result {
+                    Ok(_) => TxOutcome {
+                        signature: None,
+                        status: TxStatus::Sent,
+                        error: None,
+                    },
+                    Err(ref err) => TxOutcome::failed(format!("{:?}", err)),
+                },
+            });
+            if result.is_err() {
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
                 continue;
             }
@@ -234,20 +378,24 @@ impl Miner {
                 Ok(pool_member_onchain) => pool_member_onchain,
             };
             // Print progress
-            println!(
+            status(format!(
                 "Claimable ORE balance: {}",
                 amount_u64_to_string(pool_member_onchain.balance)
-            );
+            ));
             if last_hash_at.gt(&0) {
-                println!(
+                status(format!(
                     "Change of ORE credits in pool: {}",
                     amount_u64_to_string(
                         pool_member.total_balance.saturating_sub(last_balance) as u64
                     )
-                )
+                ))
             }
+            // Claim rewards, if due
+            auto_claimer
+                .check(self, pool_member_onchain.balance, Some(pool.pool_url.clone()))
+                .await;
 
-            if !is_mining.load(Ordering::SeqCst) {
+            if !control.is_mining() {
                 break;
             }
         }
@@ -255,138 +403,79 @@ impl Miner {
         Ok(())
     }
 
+    /// The backend for a mining run, pinned per `--core-list`/`--pin` and spreading
+    /// work across `--worker`s if any were given.
+    fn mining_backend(&self, args: &MineArgs) -> Arc<dyn HashBackend> {
+        let local = self.backend(CpuBackend::new(
+            CoreAllocation::new(&args.core_list, args.pin),
+            Throttle::new(args),
+        ));
+        if args.workers.is_empty() {
+            return local;
+        }
+        status(format!("ore-lib: Coordinating {} worker(s)", args.workers.len()));
+        Arc::new(ClusterBackend::new(local, args.workers.clone()))
+    }
+
     async fn find_hash_par(
-        challenge: [u8; 32],
-        cutoff_time: u64,
-        cores: u64,
-        min_difficulty: u32,
-        nonce_indices: &[u64],
-        is_mining: &Arc<AtomicBool>,
-    ) -> Solution {
-        // Dispatch job to each thread
+        &self,
+        backend: Arc<dyn HashBackend>,
+        job: HashJob,
+        control: &MiningControl,
+    ) -> Result<Solution, Error> {
+        backend.prepare(&job.challenge)?;
+
+        // Dispatch job to the backend
         let progress_bar = Arc::new(spinner::new_progress_bar());
-        let global_best_difficulty = Arc::new(RwLock::new(0u32));
         progress_bar.set_message("Mining...");
-        let core_ids = core_affinity::get_core_ids().unwrap();
-        let core_ids = core_ids.into_iter().filter(|id| id.id < (cores as usize));
-        let handles: Vec<_> = core_ids
-            .map(|i| {
-                let global_best_difficulty = Arc::clone(&global_best_difficulty);
-                let is_mining = Arc::clone(&is_mining);
-                std::thread::spawn({
-                    let progress_bar = progress_bar.clone();
-                    let nonce = nonce_indices[i.id];
-                    let mut memory = equix::SolverMemory::new();
-                    move || {
-                        // Pin to core
-                        let _ = core_affinity::set_for_current(i);
-
-                        // Start hashing
-                        let timer = Instant::now();
-                        let mut nonce = nonce;
-                        let mut best_nonce = nonce;
-                        let mut best_difficulty = 0;
-                        let mut best_hash = Hash::default();
-                        loop {
-                            println!("Find hash loop");
-                            if !is_mining.load(Ordering::SeqCst) {
-                                break;
-                            }
-
-                            // Get hashes
-                            let hxs = drillx::hashes_with_memory(
-                                &mut memory,
-                                &challenge,
-                                &nonce.to_le_bytes(),
-                            );
-
-                            // Look for best difficulty score in all hashes
-                            for hx in hxs {
-                                if !is_mining.load(Ordering::SeqCst) {
-                                    break;
-                                }
-                                let difficulty = hx.difficulty();
-                                if difficulty.gt(&best_difficulty) {
-                                    best_nonce = nonce;
-                                    best_difficulty = difficulty;
-                                    best_hash = hx;
-                                    if best_difficulty.gt(&*global_best_difficulty.read().unwrap())
-                                    {
-                                        *global_best_difficulty.write().unwrap() = best_difficulty;
-                                    }
-                                }
-                            }
-
-                            // Exit if time has elapsed
-                            if nonce % 100 == 0 {
-                                let global_best_difficulty =
-                                    *global_best_difficulty.read().unwrap();
-                                if timer.elapsed().as_secs().ge(&cutoff_time) {
-                                    if i.id == 0 {
-                                        progress_bar.set_message(format!(
-                                            "Mining... (difficulty {})",
-                                            global_best_difficulty,
-                                        ));
-                                    }
-                                    if global_best_difficulty.ge(&min_difficulty) {
-                                        // Mine until min difficulty has been met
-                                        break;
-                                    }
-                                } else if i.id == 0 {
-                                    progress_bar.set_message(format!(
-                                        "Mining... (difficulty {}, time {})",
-                                        global_best_difficulty,
-                                        format_duration(
-                                            cutoff_time.saturating_sub(timer.elapsed().as_secs())
-                                                as u32
-                                        ),
-                                    ));
-                                }
-                            }
-
-                            // Increment nonce
-                            nonce += 1;
-                        }
-
-                        // Return the best nonce
-                        (best_nonce, best_difficulty, best_hash)
-                    }
-                })
-            })
-            .collect();
-
-        // Join handles and return best nonce
-        let mut best_nonce = 0;
-        let mut best_difficulty = 0;
-        let mut best_hash = Hash::default();
-        for h in handles {
-            if let Ok((nonce, difficulty, hash)) = h.join() {
-                if difficulty > best_difficulty {
-                    best_difficulty = difficulty;
-                    best_nonce = nonce;
-                    best_hash = hash;
-                }
+        let progress = Arc::new(HashProgress::default());
+        let timer = Instant::now();
+        let cutoff_time = job.policy.cutoff_time;
+        let handle = tokio::task::spawn_blocking({
+            let progress = Arc::clone(&progress);
+            let control = control.clone();
+            move || backend.run(&job, &progress, &control)
+        });
+
+        // Report progress until the backend returns
+        while !handle.is_finished() {
+            let elapsed = timer.elapsed().as_secs();
+            if elapsed.ge(&cutoff_time) {
+                progress_bar.set_message(format!(
+                    "Mining... (difficulty {})",
+                    progress.best_difficulty(),
+                ));
+            } else {
+                progress_bar.set_message(format!(
+                    "Mining... (difficulty {}, time {})",
+                    progress.best_difficulty(),
+                    format_duration(cutoff_time.saturating_sub(elapsed) as u32),
+                ));
             }
+            tokio::time::sleep(Duration::from_millis(100)).await;
         }
+        let result = handle
+            .await
+            .map_err(|err| Error::Internal(format!("hashing failed: {}", err)))?;
 
         // Update log
         progress_bar.finish_with_message(format!(
             "Best hash: {} (difficulty {})",
-            bs58::encode(best_hash.h).into_string(),
-            best_difficulty
+            bs58::encode(result.solution.to_hash().h).into_string(),
+            result.difficulty
         ));
 
-        Solution::new(best_hash.d, best_nonce.to_le_bytes())
+        Ok(result.solution)
     }
 
     pub fn check_num_cores(&self, cores: u64) {
         let num_cores = num_cpus::get() as u64;
         if cores.gt(&num_cores) {
-            println!(
+            status(format!(
                 "{} Cannot exceeds available cores ({})",
                 "WARNING".bold().yellow(),
                 num_cores
-            );
+            ));
         }
     }
 
@@ -408,27 +497,29 @@ impl Miner {
             .max(0) as u64
     }
 
-    async fn find_bus(&self) -> Pubkey {
-        // Fetch the bus with the largest balance
-        if let Ok(accounts) = self.rpc_client.get_multiple_accounts(&BUS_ADDRESSES).await {
-            let mut top_bus_balance: u64 = 0;
-            let mut top_bus = BUS_ADDRESSES[0];
-            for account in accounts {
-                if let Some(account) = account {
-                    if let Ok(bus) = Bus::try_from_bytes(&account.data) {
-                        if bus.rewards.gt(&top_bus_balance) {
-                            top_bus_balance = bus.rewards;
-                            top_bus = BUS_ADDRESSES[bus.id as usize];
-                        }
-                    }
-                }
-            }
-            return top_bus;
+    /// Sleeps until a solution for a challenge last hashed at `last_hash_at` is no
+    /// longer rejected as spam, i.e. until a minute has passed less the tolerance.
+    async fn wait_for_submission_window(&self, last_hash_at: i64) {
+        let wait = self.get_cutoff(last_hash_at, TOLERANCE as u64).await;
+        if wait.gt(&0) {
+            status(format!("  Waiting {}s for the submission window", wait));
+            tokio::time::sleep(Duration::from_secs(wait)).await;
         }
+    }
 
-        // Otherwise return a random bus
-        let i = rand::thread_rng().gen_range(0..BUS_COUNT);
-        BUS_ADDRESSES[i]
+    async fn find_bus(&self, selector: &mut BusSelector, expected_reward: u64) -> BusChoice {
+        // Fetch the bus balances
+        let busses: Vec<Bus> = match self.rpc_client.get_multiple_accounts(&BUS_ADDRESSES).await {
+            Ok(accounts) => accounts
+                .into_iter()
+                .flatten()
+                .filter_map(|account| Bus::try_from_bytes(&account.data).ok().copied())
+                .collect(),
+            Err(_) => vec![],
+        };
+
+        // Pick one per the selector strategy
+        selector.select(&busses, expected_reward)
     }
 }
 
@@ -441,66 +532,100 @@ struct BoostData {
 }
 
 impl BoostData {
-    fn to_vec(boost_data: &Option<BoostData>) -> Vec<Pubkey> {
-        match boost_data {
-            Some(boost_data) => {
-                vec![boost_data.boost_address, boost_data.stake_address]
-            }
-            None => vec![],
-        }
+    /// The accounts the mine instruction takes for this boost.
+    fn accounts(&self) -> [Pubkey; 2] {
+        [self.boost_address, self.stake_address]
     }
 }
 
+/// Fetches and checks the boosts to mine with. Each must be a distinct, unexpired boost
+/// that `authority` has stake in, and there can be at most `MAX_BOOSTS` of them.
 async fn fetch_boost_data(
     rpc: Arc<RpcClient>,
     authority: Pubkey,
-    mint_address: &Option<String>,
-) -> Option<BoostData> {
-    let Some(mint_address) = mint_address else {
-        return None;
-    };
-    let mint_address = Pubkey::from_str(&mint_address).unwrap();
-    let boost_address = boost_pda(mint_address).0;
-    let stake_address = stake_pda(authority, boost_address).0;
-    let mint = rpc
-        .get_account_data(&mint_address)
-        .await
-        .map(|data| Mint::unpack(&data).unwrap())
-        .unwrap();
-    let metadata = rpc
-        .get_account_data(&Metadata::find_pda(&mint_address).0)
-        .await
-        .ok()
-        .map(|data| Metadata::from_bytes(&data).unwrap());
-    Some(BoostData {
-        boost_address,
-        stake_address,
-        mint,
-        metadata,
-    })
+    mints: &[Pubkey],
+) -> Result<Vec<BoostData>, Error> {
+    if mints.len().gt(&MAX_BOOSTS) {
+        return Err(Error::Internal(format!(
+            "at most {} boosts can be applied, got {}",
+            MAX_BOOSTS,
+            mints.len()
+        )));
+    }
+    let now = chrono::Utc::now().timestamp();
+    let mut boost_data = Vec::with_capacity(mints.len());
+    for (i, mint_address) in mints.iter().enumerate() {
+        let invalid =
+            |reason: &str| Error::InvalidBoost(mint_address.to_string(), reason.to_string());
+        if mints[..i].contains(mint_address) {
+            return Err(invalid("passed more than once"));
+        }
+        let boost_address = boost_pda(*mint_address).0;
+        let stake_address = stake_pda(authority, boost_address).0;
+        let accounts = rpc
+            .get_multiple_accounts(&[
+                *mint_address,
+                boost_address,
+                stake_address,
+                Metadata::find_pda(mint_address).0,
+            ])
+            .await?;
+        let mint = accounts[0]
+            .as_ref()
+            .and_then(|account| StateWithExtensions::<Mint>::unpack(&account.data).ok())
+            .map(|mint| mint.base)
+            .ok_or_else(|| invalid("not a token mint"))?;
+        let boost = accounts[1]
+            .as_ref()
+            .and_then(|account| Boost::try_from_bytes(&account.data).ok().copied())
+            .ok_or_else(|| invalid("no boost exists for this mint"))?;
+        if boost.expires_at.le(&now) {
+            return Err(invalid("the boost has expired"));
+        }
+        if accounts[2].is_none() {
+            return Err(invalid("you have no stake in this boost"));
+        }
+        let metadata = accounts[3]
+            .as_ref()
+            .and_then(|account| Metadata::from_bytes(&account.data).ok());
+        boost_data.push(BoostData {
+            boost_address,
+            stake_address,
+            mint,
+            metadata,
+        });
+    }
+    Ok(boost_data)
 }
 
-async fn log_boost_data(rpc: Arc<RpcClient>, boost_data: &Option<BoostData>, id: u64) {
-    if let Some(boost_data) = boost_data {
-        let boost = get_boost(&rpc, boost_data.boost_address).await;
-        let stake = get_stake(&rpc, boost_data.stake_address).await;
-        let multiplier =
-            (boost.multiplier as f64) * (stake.balance as f64) / (boost.total_stake as f64);
-        println!(
-            "  Boost {}: {:12}x ({})",
-            id,
-            multiplier,
-            format!(
-                "{} of {}{}",
-                stake.balance as f64 / 10f64.powf(boost_data.mint.decimals as f64),
-                boost.total_stake as f64 / 10f64.powf(boost_data.mint.decimals as f64),
-                boost_data
-                    .clone()
-                    .metadata
-                    .map_or("".to_string(), |m| format!(" {}", m.symbol))
-            )
-        );
-    }
+async fn log_boost_data(
+    rpc: Arc<RpcClient>,
+    boost_data: &BoostData,
+    id: usize,
+) -> Option<BoostShare> {
+    let accounts = rpc
+        .get_multiple_accounts(&[boost_data.boost_address, boost_data.stake_address])
+        .await
+        .ok()?;
+    let boost = Boost::try_from_bytes(&accounts[0].as_ref()?.data).ok()?;
+    let stake = Stake::try_from_bytes(&accounts[1].as_ref()?.data).ok()?;
+    let multiplier =
+        (boost.multiplier as f64) * (stake.balance as f64) / (boost.total_stake as f64);
+    status(format!(
+        "  Boost {}: {:12}x ({})",
+        id,
+        multiplier,
+        format!(
+            "{} of {}{}",
+            stake.balance as f64 / 10f64.powf(boost_data.mint.decimals as f64),
+            boost.total_stake as f64 / 10f64.powf(boost_data.mint.decimals as f64),
+            boost_data
+                .clone()
+                .metadata
+                .map_or("".to_string(), |m| format!(" {}", m.symbol))
+        )
+    ));
+    Some(BoostShare::new(boost, stake))
 }
 
 fn format_duration(seconds: u32) -> String {
@@ -1,18 +1,17 @@
-mod balance;
-mod benchmark;
-mod busses;
-mod claim;
-mod close;
-mod config;
-mod mine;
-mod proof;
-mod rewards;
-mod stake;
-mod unstake;
-mod transfer;
-mod upgrade;
-
-pub use crate::args::*;
-pub use crate::utils::*;
-pub use crate::miner::*;
-pub use crate::cu_limits::*;
+pub(crate) mod balance;
+pub(crate) mod benchmark;
+pub(crate) mod boosts;
+pub(crate) mod busses;
+pub(crate) mod claim;
+pub(crate) mod close;
+pub(crate) mod config;
+mod estimate;
+pub(crate) mod mine;
+pub(crate) mod proof;
+pub(crate) mod rewards;
+pub(crate) mod stake;
+pub(crate) mod unstake;
+pub(crate) mod transfer;
+pub(crate) mod transfer_batch;
+pub(crate) mod upgrade;
+mod worker;
@@ -1,42 +1,77 @@
 use std::str::FromStr;
 
-use ore_api::consts::TOKEN_DECIMALS;
+use ore_api::{consts::TOKEN_DECIMALS, state::Proof};
+use serde::Serialize;
 use solana_program::pubkey::Pubkey;
 use solana_sdk::signature::Signer;
 use spl_token::amount_to_ui_amount;
+use steel::AccountDeserialize;
 
-use crate::{
-    args::ProofArgs,
-    utils::{get_proof, proof_pubkey},
-    Miner,
-};
+use crate::{args::ProofArgs, error::Error, output, utils::proof_pubkey, Miner};
+
+/// A proof account, with amounts in grains.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProofInfo {
+    pub address: String,
+    pub authority: String,
+    pub balance: u64,
+    pub last_hash: String,
+    pub last_hash_at: i64,
+    pub last_stake_at: i64,
+    pub miner: String,
+    pub total_hashes: u64,
+    pub total_rewards: u64,
+}
 
 impl Miner {
     pub async fn proof(&self, args: ProofArgs) {
-        let signer = self.signer();
-        let address = if let Some(address) = args.address {
-            Pubkey::from_str(&address).unwrap()
+        let address = if let Some(address) = &args.address {
+            if let Ok(address) = Pubkey::from_str(address) {
+                Some(address)
+            } else {
+                output::print_error(format_args!("Invalid address: {:?}", address));
+                return;
+            }
         } else {
-            proof_pubkey(signer.pubkey())
+            None
         };
-        let proof = get_proof(&self.rpc_client, address).await;
-        println!("Address: {:?}", address);
-        println!("Authority: {:?}", proof.authority);
-        println!(
-            "Balance: {:?} ORE",
-            amount_to_ui_amount(proof.balance, TOKEN_DECIMALS)
-        );
-        println!(
-            "Last hash: {}",
-            solana_sdk::hash::Hash::new_from_array(proof.last_hash).to_string()
-        );
-        println!("Last hash at: {:?}", proof.last_hash_at);
-        println!("Last stake at: {:?}", proof.last_stake_at);
-        println!("Miner: {:?}", proof.miner);
-        println!("Total hashes: {:?}", proof.total_hashes);
-        println!(
-            "Total rewards: {:?} ORE",
-            amount_to_ui_amount(proof.total_rewards, TOKEN_DECIMALS)
-        );
+        match self.get_proof_info(address).await {
+            Ok(proof) => output::print(&proof, |proof| {
+                println!("Address: {}", proof.address);
+                println!("Authority: {}", proof.authority);
+                println!(
+                    "Balance: {:?} ORE",
+                    amount_to_ui_amount(proof.balance, TOKEN_DECIMALS)
+                );
+                println!("Last hash: {}", proof.last_hash);
+                println!("Last hash at: {:?}", proof.last_hash_at);
+                println!("Last stake at: {:?}", proof.last_stake_at);
+                println!("Miner: {}", proof.miner);
+                println!("Total hashes: {:?}", proof.total_hashes);
+                println!(
+                    "Total rewards: {:?} ORE",
+                    amount_to_ui_amount(proof.total_rewards, TOKEN_DECIMALS)
+                );
+            }),
+            Err(err) => output::print_error(err),
+        }
+    }
+
+    /// The proof at `address`, defaulting to the signer's proof.
+    pub async fn get_proof_info(&self, address: Option<Pubkey>) -> Result<ProofInfo, Error> {
+        let address = address.unwrap_or_else(|| proof_pubkey(self.signer().pubkey()));
+        let data = self.rpc_client.get_account_data(&address).await?;
+        let proof = Proof::try_from_bytes(&data)?;
+        Ok(ProofInfo {
+            address: address.to_string(),
+            authority: proof.authority.to_string(),
+            balance: proof.balance,
+            last_hash: solana_sdk::hash::Hash::new_from_array(proof.last_hash).to_string(),
+            last_hash_at: proof.last_hash_at,
+            last_stake_at: proof.last_stake_at,
+            miner: proof.miner.to_string(),
+            total_hashes: proof.total_hashes,
+            total_rewards: proof.total_rewards,
+        })
     }
 }
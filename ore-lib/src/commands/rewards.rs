@@ -1,28 +1,52 @@
+use ore_api::{consts::CONFIG_ADDRESS, state::Config};
+use serde::Serialize;
+use steel::AccountDeserialize;
+
 use crate::{
-    utils::{amount_u64_to_string, get_config},
+    error::Error,
+    output,
+    utils::{amount_u64_to_string, reward_for_difficulty},
     Miner,
 };
 
+/// The base reward for a hash of some difficulty, in grains.
+#[derive(Clone, Debug, Serialize)]
+pub struct RewardRate {
+    pub difficulty: u32,
+    pub reward: u64,
+}
+
 impl Miner {
     pub async fn rewards(&self) {
-        let config = get_config(&self.rpc_client).await;
-        let base_reward_rate = config.base_reward_rate;
-
-        let mut s = format!(
-            "{}: {} ORE",
-            config.min_difficulty,
-            amount_u64_to_string(base_reward_rate)
-        )
-        .to_string();
-        for i in 1..32 {
-            let reward_rate = base_reward_rate.saturating_mul(2u64.saturating_pow(i));
-            s = format!(
-                "{}\n{}: {} ORE",
-                s,
-                config.min_difficulty as u32 + i,
-                amount_u64_to_string(reward_rate)
-            );
+        match self.get_rewards().await {
+            Ok(rates) => output::print(&rates, |rates| {
+                let s = rates
+                    .iter()
+                    .map(|rate| {
+                        format!(
+                            "{}: {} ORE",
+                            rate.difficulty,
+                            amount_u64_to_string(rate.reward)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                println!("{}", s);
+            }),
+            Err(err) => output::print_error(err),
         }
-        println!("{}", s);
+    }
+
+    /// The reward rates for the 32 difficulties starting at the min difficulty.
+    pub async fn get_rewards(&self) -> Result<Vec<RewardRate>, Error> {
+        let data = self.rpc_client.get_account_data(&CONFIG_ADDRESS).await?;
+        let config = Config::try_from_bytes(&data)?;
+        let min_difficulty = config.min_difficulty as u32;
+        Ok((min_difficulty..min_difficulty + 32)
+            .map(|difficulty| RewardRate {
+                difficulty,
+                reward: reward_for_difficulty(config, difficulty),
+            })
+            .collect())
     }
 }
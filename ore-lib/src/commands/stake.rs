@@ -3,126 +3,184 @@ use std::str::FromStr;
 use colored::*;
 use ore_boost_api::state::{boost_pda, stake_pda, Boost, Stake};
 use ore_pool_api::state::{share_pda, Share};
-use solana_program::{program_pack::Pack, pubkey::Pubkey};
-use solana_sdk::{signature::Signer, transaction::Transaction};
-use spl_token::state::Mint;
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
 use steel::AccountDeserialize;
 
+use super::unstake::UnstakeOutput;
 use crate::{
-    args::{StakeArgs, StakeCommand, StakeDepositArgs, StakeWithdrawArgs},
+    args::{StakeArgs, StakeCommand, StakeDepositArgs, StakeWithdrawArgs, UnstakeArgs},
     cu_limits::CU_LIMIT_CLAIM,
     error::Error,
+    output::{self, status, TxOutcome},
     pool::Pool,
     send_and_confirm::ComputeBudget,
+    token_account::TokenMint,
     Miner,
 };
 
+/// A boost and a stake in it.
+#[derive(Clone, Debug, Serialize)]
+pub struct StakeInfo {
+    pub mint: String,
+    pub boost_address: String,
+    pub multiplier: u64,
+    pub expires_at: i64,
+    pub total_stake: u64,
+    pub stake_address: String,
+    pub stake_balance: u64,
+    pub last_stake_at: i64,
+    /// Set if the stake is held by a pool.
+    pub pool: Option<PoolShareInfo>,
+}
+
+/// The outcome of a stake deposit.
+#[derive(Clone, Debug, Serialize)]
+pub struct StakeTxOutput {
+    pub mint: String,
+    /// In the mint's base units.
+    pub amount: u64,
+    #[serde(flatten)]
+    pub tx: TxOutcome,
+}
+
+/// A member's share of a pool's stake.
+#[derive(Clone, Debug, Serialize)]
+pub struct PoolShareInfo {
+    pub url: String,
+    pub address: String,
+    pub share_address: String,
+    pub share_balance: u64,
+}
+
 impl Miner {
     pub async fn stake(&self, args: StakeArgs) {
-        match args.command.clone() {
-            StakeCommand::Get(_) => self.stake_get(args).await.unwrap(),
-            StakeCommand::Deposit(subargs) => self.stake_deposit(subargs, args).await.unwrap(),
-            StakeCommand::Withdraw(subargs) => self.stake_withdraw(subargs, args).await.unwrap(),
+        let result = match args.command.clone() {
+            StakeCommand::Get(_) => self.stake_get(args).await,
+            StakeCommand::Deposit(subargs) => self
+                .stake_deposit(subargs, args)
+                .await
+                .map(|tx| output::print(&tx, |_| {})),
+            StakeCommand::Withdraw(subargs) => self
+                .stake_withdraw(subargs, args)
+                .await
+                .map(|tx| output::print(&tx, |_| {})),
+        };
+        if let Err(err) = result {
+            output::print_error(err);
         }
     }
 
     async fn stake_get(&self, args: StakeArgs) -> Result<(), Error> {
-        match args.pool_url.clone() {
-            None => self.stake_get_solo(args).await,
-            Some(ref pool_url) => self.stake_get_pool(args, pool_url).await,
-        }
+        let mint_address = Pubkey::from_str(&args.mint)?;
+        let info = self.get_stake_info(mint_address, args.pool_url).await?;
+        output::print(&info, |info| {
+            match info.pool {
+                None => {
+                    println!("{}", "Stake".bold());
+                    println!("Address: {}", info.stake_address);
+                    println!(
+                        "Balance: {} ({:.8}% of total)",
+                        info.stake_balance,
+                        (info.stake_balance as f64 / info.total_stake as f64) * 100f64
+                    );
+                    println!("Last stake at: {}", info.last_stake_at);
+                }
+                Some(ref pool) => {
+                    println!("{}", "Share".bold());
+                    println!("Address: {}", pool.share_address);
+                    println!(
+                        "Balance: {} ({:.8}% of pool)",
+                        pool.share_balance,
+                        (pool.share_balance as f64 / info.stake_balance as f64) * 100f64
+                    );
+                    println!("\n{}", "Pool".bold());
+                    println!("Address: {}", pool.address);
+                    println!(
+                        "Balance: {} ({:.8}% of total)",
+                        info.stake_balance,
+                        (info.stake_balance as f64 / info.total_stake as f64) * 100f64
+                    );
+                    println!("URL: {}", pool.url);
+                    println!("Last stake at: {}", info.last_stake_at);
+                }
+            }
+            println!("\n{}", "Boost".bold());
+            println!("Balance: {}", info.total_stake);
+            println!("Mint: {}", info.mint);
+            println!("Multiplier: {}x", info.multiplier);
+            println!("Expires at: {}", info.expires_at);
+        });
+        Ok(())
     }
 
-    async fn stake_get_solo(&self, args: StakeArgs) -> Result<(), Error> {
-        let mint_address = Pubkey::from_str(&args.mint).unwrap();
-        let boost_address = boost_pda(mint_address).0;
-        let stake_address = stake_pda(self.signer().pubkey(), boost_address).0;
-        let Ok(boost_data) = self.rpc_client.get_account_data(&boost_address).await else {
-            return Ok(());
-        };
-        let Ok(boost) = Boost::try_from_bytes(&boost_data) else {
-            return Ok(());
-        };
-        let Ok(stake_data) = self.rpc_client.get_account_data(&stake_address).await else {
-            return Ok(());
+    /// The boost for `mint` and the stake in it, held by the signer or, given a
+    /// `pool_url`, by that pool on the signer's behalf.
+    pub async fn get_stake_info(
+        &self,
+        mint_address: Pubkey,
+        pool_url: Option<String>,
+    ) -> Result<StakeInfo, Error> {
+        // Find the stake authority
+        let pool = match pool_url {
+            None => None,
+            Some(pool_url) => {
+                let pool = Pool {
+                    http_client: reqwest::Client::new(),
+                    pool_url,
+                };
+                let pool_address = pool.get_pool_address().await?.address;
+                Some((pool, pool_address))
+            }
         };
-        let Ok(stake) = Stake::try_from_bytes(&stake_data) else {
-            return Ok(());
+        let authority = match &pool {
+            None => self.signer().pubkey(),
+            Some((_, pool_address)) => *pool_address,
         };
-        println!("{}", "Stake".bold());
-        println!("Address: {}", stake_address);
-        println!(
-            "Balance: {} ({:.8}% of total)",
-            stake.balance,
-            (stake.balance as f64 / boost.total_stake as f64) * 100f64
-        );
-        println!("Last stake at: {}", stake.last_stake_at);
-        println!("\n{}", "Boost".bold());
-        println!("Balance: {}", boost.total_stake);
-        println!("Mint: {}", mint_address);
-        println!("Balance: {}", boost.total_stake);
-        println!("Multiplier: {}x", boost.multiplier);
-        println!("Expires at: {}", boost.expires_at);
-        Ok(())
-    }
 
-    async fn stake_get_pool(&self, args: StakeArgs, pool_url: &String) -> Result<(), Error> {
-        let pool = Pool {
-            http_client: reqwest::Client::new(),
-            pool_url: pool_url.clone(),
-        };
-        let pool_address = pool.get_pool_address().await?.address;
-        let mint_address = Pubkey::from_str(&args.mint).unwrap();
+        // Fetch boost and stake
         let boost_address = boost_pda(mint_address).0;
-        let stake_address = stake_pda(pool_address, boost_address).0;
-        let share_address = share_pda(self.signer().pubkey(), pool_address, mint_address).0;
-        let Ok(boost_data) = self.rpc_client.get_account_data(&boost_address).await else {
-            return Ok(());
-        };
-        let Ok(boost) = Boost::try_from_bytes(&boost_data) else {
-            return Ok(());
-        };
-        let Ok(stake_data) = self.rpc_client.get_account_data(&stake_address).await else {
-            return Ok(());
-        };
-        let Ok(stake) = Stake::try_from_bytes(&stake_data) else {
-            return Ok(());
-        };
-        let Ok(share_data) = self.rpc_client.get_account_data(&share_address).await else {
-            return Ok(());
-        };
-        let Ok(share) = Share::try_from_bytes(&share_data) else {
-            return Ok(());
+        let stake_address = stake_pda(authority, boost_address).0;
+        let boost_data = self.rpc_client.get_account_data(&boost_address).await?;
+        let boost = Boost::try_from_bytes(&boost_data)?;
+        let stake_data = self.rpc_client.get_account_data(&stake_address).await?;
+        let stake = Stake::try_from_bytes(&stake_data)?;
+
+        // Fetch the signer's share of the pool stake
+        let pool = match pool {
+            None => None,
+            Some((pool, pool_address)) => {
+                let share_address = share_pda(self.signer().pubkey(), pool_address, mint_address).0;
+                let share_data = self.rpc_client.get_account_data(&share_address).await?;
+                let share = Share::try_from_bytes(&share_data)?;
+                Some(PoolShareInfo {
+                    url: pool.pool_url,
+                    address: pool_address.to_string(),
+                    share_address: share_address.to_string(),
+                    share_balance: share.balance,
+                })
+            }
         };
-        println!("{}", "Share".bold());
-        println!("Address: {}", share_address);
-        println!(
-            "Balance: {} ({:.8}% of pool)",
-            share.balance,
-            (share.balance as f64 / stake.balance as f64) * 100f64
-        );
-        println!("\n{}", "Pool".bold());
-        println!("Address: {}", pool_address);
-        println!(
-            "Balance: {} ({:.8}% of total)",
-            stake.balance,
-            (stake.balance as f64 / boost.total_stake as f64) * 100f64
-        );
-        println!("URL: {}", pool_url);
-        println!("Last stake at: {}", stake.last_stake_at);
-        println!("\n{}", "Boost".bold());
-        println!("Balance: {}", boost.total_stake);
-        println!("Mint: {}", mint_address);
-        println!("Multiplier: {}x", boost.multiplier);
-        println!("Expires at: {}", boost.expires_at);
-        Ok(())
+
+        Ok(StakeInfo {
+            mint: mint_address.to_string(),
+            boost_address: boost_address.to_string(),
+            multiplier: boost.multiplier,
+            expires_at: boost.expires_at,
+            total_stake: boost.total_stake,
+            stake_address: stake_address.to_string(),
+            stake_balance: stake.balance,
+            last_stake_at: stake.last_stake_at,
+            pool,
+        })
     }
 
     async fn stake_deposit(
         &self,
         args: StakeDepositArgs,
         stake_args: StakeArgs,
-    ) -> Result<(), Error> {
+    ) -> Result<StakeTxOutput, Error> {
         match stake_args.pool_url.clone() {
             None => self.stake_deposit_solo(args, stake_args).await,
             Some(ref pool_url) => self.stake_deposit_pool(args, stake_args, pool_url).await,
@@ -132,69 +190,78 @@ impl Miner {
         &self,
         args: StakeDepositArgs,
         stake_args: StakeArgs,
-    ) -> Result<(), Error> {
+    ) -> Result<StakeTxOutput, Error> {
         // Parse mint address
-        let mint_address = Pubkey::from_str(&stake_args.mint).unwrap();
+        let mint_address = Pubkey::from_str(&stake_args.mint)?;
+
+        // Get mint
+        let Ok(mint) = TokenMint::fetch(&self.rpc_client, mint_address).await else {
+            return Err(Error::Internal("failed to fetch mint address".to_string()));
+        };
 
         // Get signer
         let signer = self.signer();
         let sender = match &args.token_account {
-            Some(address) => {
-                Pubkey::from_str(&address).expect("Failed to parse token account address")
-            }
-            None => spl_associated_token_account::get_associated_token_address(
-                &signer.pubkey(),
-                &mint_address,
-            ),
+            Some(address) => Pubkey::from_str(address)?,
+            None => mint.ata(&signer.pubkey()),
         };
 
         // Get token account
         let Ok(Some(token_account)) = self.rpc_client.get_token_account(&sender).await else {
-            println!("Failed to fetch token account");
-            return Ok(());
-        };
-
-        let Ok(mint_data) = self.rpc_client.get_account_data(&mint_address).await else {
-            println!("Failed to fetch mint address");
-            return Ok(());
+            return Err(Error::Internal("failed to fetch token account".to_string()));
         };
-        let mint = Mint::unpack(&mint_data).unwrap();
 
         // Parse amount
         let amount: u64 = if let Some(amount) = args.amount {
             (amount * 10f64.powf(mint.decimals as f64)) as u64
         } else {
-            u64::from_str(token_account.token_amount.amount.as_str())
-                .expect("Failed to parse token balance")
+            u64::from_str(token_account.token_amount.amount.as_str())?
         };
 
+        self.deposit_stake(mint_address, amount).await
+    }
+
+    /// Deposits `amount` of `mint_address`, in base units, from the signer's associated
+    /// token account into its stake account, opening the stake account if needed.
+    pub async fn deposit_stake(
+        &self,
+        mint_address: Pubkey,
+        amount: u64,
+    ) -> Result<StakeTxOutput, Error> {
         // Get addresses
+        let signer = self.signer();
         let boost_address = boost_pda(mint_address).0;
         let stake_address = stake_pda(signer.pubkey(), boost_address).0;
+        let mint = TokenMint::fetch(&self.rpc_client, mint_address).await?;
 
         // Fetch boost
         let Ok(boost_account_data) = self.rpc_client.get_account_data(&boost_address).await else {
-            println!("Failed to fetch boost account");
-            return Ok(());
+            return Err(Error::Internal("failed to fetch boost account".to_string()));
         };
-        let _ = Boost::try_from_bytes(&boost_account_data).unwrap();
+        Boost::try_from_bytes(&boost_account_data)?;
 
         // Open stake account, if needed
         if let Err(_err) = self.rpc_client.get_account_data(&stake_address).await {
-            println!("Failed to fetch stake account");
+            status("Opening stake account...");
             let ix = ore_boost_api::sdk::open(signer.pubkey(), signer.pubkey(), mint_address);
             self.send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
-                .await
-                .ok();
+                .await?;
         }
 
         // Send tx
-        let ix = ore_boost_api::sdk::deposit(signer.pubkey(), mint_address, amount);
-        self.send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
-            .await
-            .ok();
+        let ix = mint.with_program(
+            ore_boost_api::sdk::deposit(signer.pubkey(), mint_address, amount),
+            &[signer.pubkey(), boost_address],
+        );
+        let result = self
+            .send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
+            .await;
 
-        Ok(())
+        Ok(StakeTxOutput {
+            mint: mint_address.to_string(),
+            amount,
+            tx: TxOutcome::new(&result),
+        })
     }
 
     async fn stake_deposit_pool(
@@ -202,7 +269,7 @@ impl Miner {
         args: StakeDepositArgs,
         stake_args: StakeArgs,
         pool_url: &String,
-    ) -> Result<(), Error> {
+    ) -> Result<StakeTxOutput, Error> {
         let signer = self.signer();
         // build pool client
         let pool = Pool {
@@ -211,35 +278,45 @@ impl Miner {
         };
         // register member, if needed
         let _ = pool.post_pool_register(self).await?;
-        // fetch pool address
-        let pool_address = pool.get_pool_address().await?;
         // parse mint
         let mint = Pubkey::from_str(stake_args.mint.as_str())?;
+        // assert that mint exists
+        let mint_account = TokenMint::fetch(&self.rpc_client, mint).await?;
         // get sender token account
         let sender = match &args.token_account {
             Some(address) => Pubkey::from_str(address.as_str())?,
-            None => {
-                spl_associated_token_account::get_associated_token_address(&signer.pubkey(), &mint)
-            }
+            None => mint_account.ata(&signer.pubkey()),
         };
         // assert that sender exists
         let Ok(Some(token_account)) = self.rpc_client.get_token_account(&sender).await else {
-            println!("Failed to fetch token account");
             return Err(Error::Internal(
                 "sender token account does not exist".to_string(),
             ));
         };
-        // assert that mint exists
-        let mint_data = self.rpc_client.get_account_data(&mint).await?;
-        let mint_account = Mint::unpack(&mint_data)?;
         // parse amount
         let amount: u64 = if let Some(amount) = args.amount {
             (amount * 10f64.powf(mint_account.decimals as f64)) as u64
         } else {
             u64::from_str(token_account.token_amount.amount.as_str())?
         };
+        self.deposit_pool_stake(&pool, &mint_account, sender, amount)
+            .await
+    }
+
+    /// Deposits `amount` of `mint`, in base units, from `sender` into the pool's stake
+    /// account, crediting the signer's share.
+    pub async fn deposit_pool_stake(
+        &self,
+        pool: &Pool,
+        mint: &TokenMint,
+        sender: Pubkey,
+        amount: u64,
+    ) -> Result<StakeTxOutput, Error> {
+        let signer = self.signer();
+        // fetch pool address
+        let pool_address = pool.get_pool_address().await?;
         // derive pdas
-        let boost_address = boost_pda(mint).0;
+        let boost_address = boost_pda(mint.address).0;
         let stake_address = stake_pda(pool_address.address, boost_address).0;
         // assert that boost exists
         let boost_data = self.rpc_client.get_account_data(&boost_address).await?;
@@ -248,151 +325,39 @@ impl Miner {
         let stake_data = self.rpc_client.get_account_data(&stake_address).await?;
         let _ = Stake::try_from_bytes(stake_data.as_slice())?;
         // open share account, if needed
-        let _ = pool.post_pool_register_staker(self, &mint).await?;
+        let _ = pool.post_pool_register_staker(self, &mint.address).await?;
         // send tx
-        let ix =
-            ore_pool_api::sdk::stake(signer.pubkey(), mint, pool_address.address, sender, amount);
-        let _ = self
+        let ix = mint.with_program(
+            ore_pool_api::sdk::stake(
+                signer.pubkey(),
+                mint.address,
+                pool_address.address,
+                sender,
+                amount,
+            ),
+            &[pool_address.address, boost_address],
+        );
+        let result = self
             .send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
-            .await?;
-        Ok(())
+            .await;
+        Ok(StakeTxOutput {
+            mint: mint.address.to_string(),
+            amount,
+            tx: TxOutcome::new(&result),
+        })
     }
 
     async fn stake_withdraw(
         &self,
         args: StakeWithdrawArgs,
         stake_args: StakeArgs,
-    ) -> Result<(), Error> {
-        match stake_args.pool_url.clone() {
-            None => self.stake_withdraw_solo(args, stake_args).await,
-            Some(ref pool_url) => self.stake_withdraw_pool(args, stake_args, pool_url).await,
-        }
-    }
-
-    async fn stake_withdraw_solo(
-        &self,
-        args: StakeWithdrawArgs,
-        stake_args: StakeArgs,
-    ) -> Result<(), Error> {
-        // Parse mint address
-        let mint_address = Pubkey::from_str(&stake_args.mint).unwrap();
-
-        // Get signer
-        let signer = self.signer();
-        let beneficiary = match &args.token_account {
-            Some(address) => {
-                Pubkey::from_str(&address).expect("Failed to parse token account address")
-            }
-            None => spl_associated_token_account::get_associated_token_address(
-                &signer.pubkey(),
-                &mint_address,
-            ),
-        };
-
-        // Get token account
-        let Ok(Some(_token_account)) = self.rpc_client.get_token_account(&beneficiary).await else {
-            println!("Failed to fetch token account");
-            return Ok(());
-        };
-
-        let Ok(mint_data) = self.rpc_client.get_account_data(&mint_address).await else {
-            println!("Failed to fetch mint address");
-            return Ok(());
-        };
-        let mint = Mint::unpack(&mint_data).unwrap();
-
-        // Get addresses
-        let boost_address = boost_pda(mint_address).0;
-        let stake_address = stake_pda(signer.pubkey(), boost_address).0;
-
-        // Fetch boost
-        let Ok(boost_account_data) = self.rpc_client.get_account_data(&boost_address).await else {
-            println!("Failed to fetch boost account");
-            return Ok(());
-        };
-        let _ = Boost::try_from_bytes(&boost_account_data).unwrap();
-
-        // Fetch stake account, if needed
-        let Ok(stake_account_data) = self.rpc_client.get_account_data(&stake_address).await else {
-            println!("Failed to fetch stake account");
-            return Ok(());
-        };
-        let stake = Stake::try_from_bytes(&stake_account_data).unwrap();
-
-        // Parse amount
-        let amount: u64 = if let Some(amount) = args.amount {
-            (amount * 10f64.powf(mint.decimals as f64)) as u64
-        } else {
-            stake.balance
-        };
-
-        // Send tx
-        // TODO: benfeciary should be arg to ix builder
-        let ix = ore_boost_api::sdk::withdraw(signer.pubkey(), mint_address, amount);
-        self.send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
-            .await
-            .ok();
-
-        Ok(())
-    }
-
-    async fn stake_withdraw_pool(
-        &self,
-        args: StakeWithdrawArgs,
-        stake_args: StakeArgs,
-        pool_url: &String,
-    ) -> Result<(), Error> {
-        let signer = self.signer();
-        // build pool client
-        let pool = Pool {
-            http_client: reqwest::Client::new(),
-            pool_url: pool_url.clone(),
-        };
-        // parse mint
-        let mint_address = Pubkey::from_str(stake_args.mint.as_str())?;
-        // get beneficiary
-        let beneficiary = match &args.token_account {
-            Some(address) => Pubkey::from_str(&address)?,
-            None => spl_associated_token_account::get_associated_token_address(
-                &signer.pubkey(),
-                &mint_address,
-            ),
-        };
-        // assert that token account exists
-        let Ok(Some(_token_account)) = self.rpc_client.get_token_account(&beneficiary).await else {
-            return Err(Error::Internal("failed to fetch token account".to_string()));
-        };
-        // fetch mint account
-        let mint_data = self.rpc_client.get_account_data(&mint_address).await?;
-        let mint = Mint::unpack(&mint_data)?;
-        // assert that boost account exists
-        let boost_address = boost_pda(mint_address).0;
-        let boost_account_data = self.rpc_client.get_account_data(&boost_address).await?;
-        let _boost = Boost::try_from_bytes(boost_account_data.as_slice())?;
-        // fetch share account
-        let pool_address = pool.get_pool_address().await?;
-        let share = pool
-            .get_staker_onchain(self, pool_address.address, mint_address)
-            .await?;
-        // parse amount
-        let amount: u64 = if let Some(amount) = args.amount {
-            (amount * 10f64.powf(mint.decimals as f64)) as u64
-        } else {
-            share.balance
-        };
-        // send tx
-        let ix = ore_pool_api::sdk::unstake(
-            signer.pubkey(),
-            mint_address,
-            pool_address.address,
-            beneficiary,
-            amount,
-        );
-        let mut tx = Transaction::new_with_payer(&[ix], Some(&signer.pubkey()));
-        let hash = self.rpc_client.get_latest_blockhash().await?;
-        tx.sign(&[&signer], hash);
-        let sig = self.rpc_client.send_transaction(&tx).await?;
-        println!("{:?}", sig);
-        Ok(())
+    ) -> Result<UnstakeOutput, Error> {
+        self.unstake_tokens(&UnstakeArgs {
+            amount: args.amount,
+            mint: stake_args.mint,
+            token_account: args.token_account,
+            pool_url: stake_args.pool_url,
+        })
+        .await
     }
 }
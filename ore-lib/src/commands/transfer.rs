@@ -2,51 +2,90 @@ use std::str::FromStr;
 
 use colored::*;
 use ore_api::consts::MINT_ADDRESS;
+use serde::Serialize;
 use solana_program::pubkey::Pubkey;
 use solana_sdk::signature::Signer;
 use spl_token::amount_to_ui_amount;
 
 use crate::{
     args::TransferArgs,
-    cu_limits::CU_LIMIT_CLAIM,
+    commands::transfer_batch::log_batch_transfer,
+    cu_limits::{CU_LIMIT_CLAIM, CU_LIMIT_MEMO},
+    error::Error,
+    output::{self, TxOutcome},
     send_and_confirm::ComputeBudget,
-    utils::{amount_f64_to_u64, ask_confirm},
+    token_account::{check_memo, memo_ix, TokenMint},
+    utils::amount_f64_to_u64,
     Miner,
 };
 
+/// The outcome of a transfer.
+#[derive(Clone, Debug, Serialize)]
+pub struct TransferOutput {
+    /// In grains.
+    pub amount: u64,
+    pub recipient: String,
+    #[serde(flatten)]
+    pub tx: TxOutcome,
+}
+
 impl Miner {
     pub async fn transfer(&self, args: TransferArgs) {
+        if let Some(batch) = &args.batch {
+            match self
+                .transfer_batch(batch, args.results.clone(), args.retry_unknown)
+                .await
+            {
+                Ok(transfer) => output::print(&transfer, log_batch_transfer),
+                Err(err) => output::print_error(err),
+            }
+            return;
+        }
+        let (Some(amount), Some(to)) = (args.amount, args.to) else {
+            return output::print_error("an amount and a recipient are required");
+        };
+        let transfer = self
+            .transfer_tokens(amount, &to, args.memo.as_deref())
+            .await;
+        output::print(&transfer, |_| {});
+    }
+
+    async fn transfer_tokens(&self, amount: f64, to: &str, memo: Option<&str>) -> TransferOutput {
         let signer = self.signer();
         let pubkey = signer.pubkey();
-        let sender_tokens =
-            spl_associated_token_account::get_associated_token_address(&pubkey, &MINT_ADDRESS);
-        let mut ixs = vec![];
+
+        // Parse amount to claim
+        let amount = amount_f64_to_u64(amount);
 
         // Initialize recipient, if needed
-        let to = Pubkey::from_str(&args.to).expect("Failed to parse recipient wallet address");
-        let recipient_tokens =
-            spl_associated_token_account::get_associated_token_address(&to, &MINT_ADDRESS);
-        if self
-            .rpc_client
-            .get_token_account(&recipient_tokens)
-            .await
-            .is_err()
-        {
-            ixs.push(
-                spl_associated_token_account::instruction::create_associated_token_account(
-                    &signer.pubkey(),
-                    &to,
-                    &ore_api::consts::MINT_ADDRESS,
-                    &spl_token::id(),
-                ),
-            );
-        }
+        let failed = |err: Error| TransferOutput {
+            amount,
+            recipient: to.to_string(),
+            tx: TxOutcome::failed(format!("{:?}", err)),
+        };
+        let to = match Pubkey::from_str(to) {
+            Ok(to) => to,
+            Err(err) => return failed(err.into()),
+        };
+        let mint = match TokenMint::fetch(&self.rpc_client, MINT_ADDRESS).await {
+            Ok(mint) => mint,
+            Err(err) => return failed(err),
+        };
+        let sender_tokens = mint.ata(&pubkey);
+        let ata = match self.get_atas(&mint, &[to]).await {
+            Ok(mut atas) => atas.remove(0),
+            Err(err) => return failed(err),
+        };
+        let recipient_tokens = ata.address;
+        let mut ixs: Vec<_> = ata.create_ix.clone().into_iter().collect();
 
-        // Parse amount to claim
-        let amount = amount_f64_to_u64(args.amount);
+        // Check the recipient doesn't require a memo we lack
+        if let Err(err) = check_memo(&self.rpc_client, &recipient_tokens, memo).await {
+            return failed(err);
+        }
 
         // Confirm user wants to claim
-        if !ask_confirm(
+        if !self.confirmer.confirm(
             format!(
                 "\nYou are about to transfer {}.\n\nAre you sure you want to continue? [Y/n]",
                 format!(
@@ -57,23 +96,40 @@ impl Miner {
             )
             .as_str(),
         ) {
-            return;
+            return TransferOutput {
+                amount,
+                recipient: to.to_string(),
+                tx: TxOutcome::cancelled(),
+            };
         }
 
         // Send and confirm
+        let mut compute_units = CU_LIMIT_CLAIM + ata.compute_units();
+        if let Some(memo) = memo {
+            ixs.push(memo_ix(memo, &pubkey));
+            compute_units += CU_LIMIT_MEMO;
+        }
         ixs.push(
-            spl_token::instruction::transfer(
-                &spl_token::id(),
-                &sender_tokens,
-                &recipient_tokens,
-                &pubkey,
-                &[&pubkey],
-                amount,
-            )
-            .unwrap(),
+            mint.with_program(
+                spl_token::instruction::transfer(
+                    &spl_token::id(),
+                    &sender_tokens,
+                    &recipient_tokens,
+                    &pubkey,
+                    &[&pubkey],
+                    amount,
+                )
+                .unwrap(),
+                &[],
+            ),
         );
-        self.send_and_confirm(&ixs, ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
-            .await
-            .ok();
+        let result = self
+            .send_and_confirm(&ixs, ComputeBudget::Fixed(compute_units), false)
+            .await;
+        TransferOutput {
+            amount,
+            recipient: to.to_string(),
+            tx: TxOutcome::new(&result),
+        }
     }
 }
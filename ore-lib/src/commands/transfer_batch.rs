@@ -0,0 +1,755 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    str::FromStr,
+};
+
+use colored::*;
+use ore_api::consts::MINT_ADDRESS;
+use serde::Serialize;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_sdk::{packet::PACKET_DATA_SIZE, signature::Signer, transaction::Transaction};
+
+use crate::{
+    cu_limits::CU_LIMIT_TOKEN_TRANSFER,
+    error::Error,
+    output::status,
+    send_and_confirm::{ComputeBudget, SendError},
+    token_account::{Ata, AtaState, TokenMint},
+    utils::{amount_str_to_u64, amount_u64_to_decimal_string, amount_u64_to_string},
+    Miner,
+};
+
+/// The most compute units a transaction can request.
+const MAX_TX_COMPUTE_UNITS: u32 = 1_400_000;
+
+const RESULTS_HEADER: &str = "row,recipient,amount,status,signature,error";
+
+/// Where a batch transfer row stands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutStatus {
+    /// Not sent yet.
+    Pending,
+    Confirmed,
+    /// Landed and failed, so safe to resend.
+    Failed,
+    /// Sent without hearing back. The transfer may have landed.
+    Unknown,
+}
+
+impl PayoutStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PayoutStatus::Pending => "pending",
+            PayoutStatus::Confirmed => "confirmed",
+            PayoutStatus::Failed => "failed",
+            PayoutStatus::Unknown => "unknown",
+        }
+    }
+
+    fn parse(status: &str) -> Option<Self> {
+        match status {
+            "pending" => Some(PayoutStatus::Pending),
+            "confirmed" => Some(PayoutStatus::Confirmed),
+            "failed" => Some(PayoutStatus::Failed),
+            "unknown" => Some(PayoutStatus::Unknown),
+            _ => None,
+        }
+    }
+}
+
+/// A row of a batch transfer and its outcome.
+#[derive(Clone, Debug, Serialize)]
+pub struct PayoutRow {
+    /// The line in the batch file.
+    pub row: usize,
+    pub recipient: String,
+    /// In grains.
+    pub amount: u64,
+    pub status: PayoutStatus,
+    pub signature: Option<String>,
+    pub error: Option<String>,
+}
+
+/// The outcome of a batch transfer.
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchTransferOutput {
+    /// The results file.
+    pub results: String,
+    /// The transactions sent by this run.
+    pub transactions: usize,
+    pub cancelled: bool,
+    pub rows: Vec<PayoutRow>,
+}
+
+/// A row of a results file.
+struct ResultRow {
+    row: usize,
+    recipient: String,
+    /// In grains.
+    amount: u64,
+    status: PayoutStatus,
+    signature: Option<String>,
+    error: Option<String>,
+}
+
+/// Transfers packed into one transaction.
+#[derive(Default)]
+struct TransferChunk {
+    rows: Vec<usize>,
+    ixs: Vec<Instruction>,
+    compute_units: u32,
+    created: HashSet<Pubkey>,
+}
+
+impl Miner {
+    /// Transfers ORE to every row of the CSV file at `batch`, recording each row's
+    /// outcome in the results file and skipping rows an earlier run confirmed.
+    pub async fn transfer_batch(
+        &self,
+        batch: &str,
+        results: Option<String>,
+        retry_unknown: bool,
+    ) -> Result<BatchTransferOutput, Error> {
+        let pubkey = self.signer().pubkey();
+        let mint = TokenMint::fetch(&self.rpc_client, MINT_ADDRESS).await?;
+        let sender_tokens = mint.ata(&pubkey);
+
+        // Load the batch and any earlier results
+        let results = results.unwrap_or_else(|| {
+            Path::new(batch)
+                .with_extension("results.csv")
+                .display()
+                .to_string()
+        });
+        let mut rows = read_batch(batch)?;
+        if Path::new(&results).exists() {
+            merge_results(&mut rows, &read_results(&results)?, &results)?;
+        }
+        let unknown = rows
+            .iter()
+            .filter(|row| row.status.eq(&PayoutStatus::Unknown))
+            .count();
+        let to_send: Vec<usize> = (0..rows.len())
+            .filter(|i| match rows[*i].status {
+                PayoutStatus::Pending | PayoutStatus::Failed => true,
+                PayoutStatus::Unknown => retry_unknown,
+                PayoutStatus::Confirmed => false,
+            })
+            .collect();
+        if unknown.gt(&0) && !retry_unknown {
+            status(format!(
+                "{} Skipping {} rows with an unknown outcome. Check whether they landed, then rerun with --retry-unknown to resend them.",
+                "WARNING".bold().yellow(),
+                unknown
+            ));
+        }
+        if to_send.is_empty() {
+            return Ok(BatchTransferOutput {
+                results,
+                transactions: 0,
+                cancelled: false,
+                rows,
+            });
+        }
+
+        // Find recipients without a token account
+        let recipients: Vec<Pubkey> = to_send
+            .iter()
+            .map(|i| Pubkey::from_str(&rows[*i].recipient))
+            .collect::<Result<HashSet<_>, _>>()?
+            .into_iter()
+            .collect();
+        let atas: HashMap<Pubkey, Ata> = recipients
+            .iter()
+            .copied()
+            .zip(self.get_atas(&mint, &recipients).await?)
+            .collect();
+        let missing = atas
+            .values()
+            .filter(|ata| ata.state.eq(&AtaState::Missing))
+            .count();
+
+        // Pack transfers into transactions
+        let tip = *self.tip.read().unwrap() > 0;
+        let chunks = pack(
+            &rows,
+            &to_send,
+            &mint,
+            &atas,
+            &pubkey,
+            &self.fee_payer().pubkey(),
+            tip,
+        )?;
+
+        // Check the sender holds enough
+        let total: u64 = to_send.iter().map(|i| rows[*i].amount).sum();
+        let balance = match self.rpc_client.get_token_account(&sender_tokens).await {
+            Ok(Some(token_account)) => u64::from_str(&token_account.token_amount.amount)?,
+            _ => 0,
+        };
+        if balance.lt(&total) {
+            return Err(Error::Internal(format!(
+                "insufficient balance: {} ORE to transfer but {} ORE held",
+                amount_u64_to_string(total),
+                amount_u64_to_string(balance)
+            )));
+        }
+
+        // Confirm user wants to transfer
+        let skipped = rows.len() - to_send.len();
+        if !self.confirmer.confirm(
+            format!(
+                "\nYou are about to transfer {} to {} rows in {} transactions, creating {} token accounts.{}\nResults are written to {}.\n\nAre you sure you want to continue? [Y/n]",
+                format!("{} ORE", amount_u64_to_string(total)).bold(),
+                to_send.len(),
+                chunks.len(),
+                missing,
+                if skipped.gt(&0) {
+                    format!(" {} rows are skipped.", skipped)
+                } else {
+                    "".to_string()
+                },
+                results
+            )
+            .as_str(),
+        ) {
+            return Ok(BatchTransferOutput {
+                results,
+                transactions: 0,
+                cancelled: true,
+                rows,
+            });
+        }
+
+        // Send each transaction, recording its rows as unknown until it's resolved
+        for (n, chunk) in chunks.iter().enumerate() {
+            status(format!(
+                "Sending transaction {} of {} ({} transfers)",
+                n + 1,
+                chunks.len(),
+                chunk.rows.len()
+            ));
+            for i in chunk.rows.iter() {
+                rows[*i].status = PayoutStatus::Unknown;
+                rows[*i].signature = None;
+                rows[*i].error = None;
+            }
+            write_results(&results, &rows)?;
+            let result = self
+                .try_send_and_confirm(&chunk.ixs, ComputeBudget::Fixed(chunk.compute_units), false)
+                .await;
+            for i in chunk.rows.iter() {
+                match &result {
+                    Ok(sig) => {
+                        rows[*i].status = PayoutStatus::Confirmed;
+                        rows[*i].signature = Some(sig.to_string());
+                    }
+                    Err(err) => {
+                        rows[*i].status = match err {
+                            SendError::NotSent(_) => PayoutStatus::Pending,
+                            SendError::Unconfirmed(_) => PayoutStatus::Unknown,
+                            SendError::Failed(_) => PayoutStatus::Failed,
+                        };
+                        rows[*i].error = Some(err.to_string());
+                    }
+                }
+            }
+            write_results(&results, &rows)?;
+        }
+
+        Ok(BatchTransferOutput {
+            results,
+            transactions: chunks.len(),
+            cancelled: false,
+            rows,
+        })
+    }
+}
+
+/// The instructions paying `amount` of `mint` into `ata`, creating it first if `create`,
+/// and the compute units they need.
+fn payout_ixs(
+    mint: &TokenMint,
+    sender: &Pubkey,
+    ata: &Ata,
+    amount: u64,
+    create: bool,
+) -> Result<(Vec<Instruction>, u32), Error> {
+    let mut ixs = vec![];
+    let mut compute_units = CU_LIMIT_TOKEN_TRANSFER;
+    if create {
+        ixs.extend(ata.create_ix.clone());
+        compute_units += ata.compute_units();
+    }
+    ixs.push(mint.with_program(
+        spl_token::instruction::transfer(
+            &spl_token::id(),
+            &mint.ata(sender),
+            &ata.address,
+            sender,
+            &[sender],
+            amount,
+        )?,
+        &[],
+    ));
+    Ok((ixs, compute_units))
+}
+
+/// Packs the transfers of the `to_send` rows into as few transactions as fit, in
+/// order, creating each missing token account once per transaction.
+fn pack(
+    rows: &[PayoutRow],
+    to_send: &[usize],
+    mint: &TokenMint,
+    atas: &HashMap<Pubkey, Ata>,
+    sender: &Pubkey,
+    fee_payer: &Pubkey,
+    tip: bool,
+) -> Result<Vec<TransferChunk>, Error> {
+    let mut chunks = vec![];
+    let mut chunk = TransferChunk::default();
+    for i in to_send.iter() {
+        let recipient = Pubkey::from_str(&rows[*i].recipient)?;
+        let ata = &atas[&recipient];
+        let mut create = ata.create_ix.is_some() && !chunk.created.contains(&recipient);
+        let mut payout = payout_ixs(mint, sender, ata, rows[*i].amount, create)?;
+
+        // Start a new transaction if this one is full
+        if !chunk.rows.is_empty() {
+            let ixs = [chunk.ixs.as_slice(), payout.0.as_slice()].concat();
+            let fits = tx_size(&ixs, fee_payer, tip).le(&PACKET_DATA_SIZE)
+                && (chunk.compute_units + payout.1).le(&MAX_TX_COMPUTE_UNITS);
+            if !fits {
+                chunks.push(std::mem::take(&mut chunk));
+                create = ata.create_ix.is_some();
+                payout = payout_ixs(mint, sender, ata, rows[*i].amount, create)?;
+            }
+        }
+        if create {
+            chunk.created.insert(recipient);
+        }
+        chunk.ixs.extend(payout.0);
+        chunk.compute_units += payout.1;
+        chunk.rows.push(*i);
+    }
+    chunks.push(chunk);
+    Ok(chunks)
+}
+
+/// The serialized size of a transaction carrying `ixs`, with the compute budget
+/// instructions and tip `send_and_confirm` adds.
+fn tx_size(ixs: &[Instruction], fee_payer: &Pubkey, tip: bool) -> usize {
+    let mut final_ixs = vec![
+        solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(0),
+        solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_price(0),
+    ];
+    final_ixs.extend_from_slice(ixs);
+    if tip {
+        final_ixs.push(solana_program::system_instruction::transfer(
+            fee_payer,
+            &Pubkey::new_unique(),
+            0,
+        ));
+    }
+    let tx = Transaction::new_with_payer(&final_ixs, Some(fee_payer));
+    bincode::serialized_size(&tx).map_or(usize::MAX, |size| size as usize)
+}
+
+/// Reads `RECIPIENT_ADDRESS,AMOUNT` rows, skipping blank lines, `#` comments and a header.
+fn read_batch(path: &str) -> Result<Vec<PayoutRow>, Error> {
+    let invalid = |row: usize, msg: &str| Error::Internal(format!("{}:{}: {}", path, row, msg));
+    let mut rows = vec![];
+    let mut header = true;
+    for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let row = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [recipient, amount] = fields[..] else {
+            return Err(invalid(row, "expected RECIPIENT_ADDRESS,AMOUNT"));
+        };
+        let first = std::mem::replace(&mut header, false);
+        let (recipient, amount) = match (Pubkey::from_str(recipient), amount_str_to_u64(amount)) {
+            (Ok(recipient), Some(amount)) => (recipient, amount),
+            (Err(_), None) if first => continue,
+            (Err(_), _) => return Err(invalid(row, "invalid recipient address")),
+            (_, None) => return Err(invalid(row, "invalid amount")),
+        };
+        if amount.eq(&0) {
+            return Err(invalid(row, "amount must be positive"));
+        }
+        rows.push(PayoutRow {
+            row,
+            recipient: recipient.to_string(),
+            amount,
+            status: PayoutStatus::Pending,
+            signature: None,
+            error: None,
+        });
+    }
+    Ok(rows)
+}
+
+/// Reads a results file as written by `write_results`.
+fn read_results(path: &str) -> Result<Vec<ResultRow>, Error> {
+    let invalid = |row: usize| Error::Internal(format!("{}:{}: invalid results row", path, row));
+    let mut results = vec![];
+    for (i, line) in fs::read_to_string(path)?.lines().enumerate().skip(1) {
+        let fields: Vec<&str> = line.splitn(6, ',').collect();
+        let [row, recipient, amount, status, signature, error] = fields[..] else {
+            return Err(invalid(i + 1));
+        };
+        let (Ok(row), Some(amount), Some(status)) = (
+            usize::from_str(row),
+            amount_str_to_u64(amount),
+            PayoutStatus::parse(status),
+        ) else {
+            return Err(invalid(i + 1));
+        };
+        let error = error
+            .strip_prefix('"')
+            .and_then(|error| error.strip_suffix('"'))
+            .unwrap_or(error)
+            .replace("\"\"", "\"");
+        results.push(ResultRow {
+            row,
+            recipient: recipient.to_string(),
+            amount,
+            status,
+            signature: Some(signature.to_string()).filter(|sig| !sig.is_empty()),
+            error: Some(error).filter(|error| !error.is_empty()),
+        });
+    }
+    Ok(results)
+}
+
+/// Carries earlier outcomes over to `rows`, refusing results from a different batch.
+fn merge_results(rows: &mut [PayoutRow], results: &[ResultRow], path: &str) -> Result<(), Error> {
+    let mut by_row: HashMap<usize, &mut PayoutRow> =
+        rows.iter_mut().map(|row| (row.row, row)).collect();
+    for result in results {
+        let payout = match by_row.get_mut(&result.row) {
+            Some(payout)
+                if payout.recipient.eq(&result.recipient) && payout.amount.eq(&result.amount) =>
+            {
+                payout
+            }
+            _ => {
+                return Err(Error::Internal(format!(
+                "{} doesn't match the batch at row {}. Pass --results to start a new results file.",
+                path, result.row
+            )))
+            }
+        };
+        payout.status = result.status;
+        payout.signature = result.signature.clone();
+        payout.error = result.error.clone();
+    }
+    Ok(())
+}
+
+/// Writes every row's outcome, replacing the results file in one step so a crash
+/// can't leave it half written.
+fn write_results(path: &str, rows: &[PayoutRow]) -> Result<(), Error> {
+    let mut contents = format!("{}\n", RESULTS_HEADER);
+    for row in rows {
+        let error = row.error.as_deref().map_or("".to_string(), |error| {
+            format!("\"{}\"", error.replace('"', "\"\"").replace('\n', " "))
+        });
+        contents.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            row.row,
+            row.recipient,
+            amount_u64_to_decimal_string(row.amount),
+            row.status.as_str(),
+            row.signature.as_deref().unwrap_or(""),
+            error
+        ));
+    }
+    let tmp = format!("{}.tmp", path);
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub(super) fn log_batch_transfer(transfer: &BatchTransferOutput) {
+    if transfer.cancelled {
+        return;
+    }
+    let count = |status: PayoutStatus| {
+        transfer
+            .rows
+            .iter()
+            .filter(|row| row.status.eq(&status))
+            .count()
+    };
+    println!(
+        "{} confirmed, {} failed, {} unknown, {} pending over {} transactions",
+        count(PayoutStatus::Confirmed),
+        count(PayoutStatus::Failed),
+        count(PayoutStatus::Unknown),
+        count(PayoutStatus::Pending),
+        transfer.transactions
+    );
+    for row in transfer.rows.iter() {
+        if matches!(row.status, PayoutStatus::Failed | PayoutStatus::Unknown) {
+            println!(
+                "  Row {} to {}: {} ({})",
+                row.row,
+                row.recipient,
+                row.status.as_str(),
+                row.error.as_deref().unwrap_or("no error")
+            );
+        }
+    }
+    println!("Results: {}", transfer.results);
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::account::Account;
+
+    use super::*;
+
+    const ORE: u64 = 100_000_000_000;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("ore-batch-{}-{}.csv", std::process::id(), name))
+            .display()
+            .to_string()
+    }
+
+    fn write_batch(name: &str, contents: &str) -> String {
+        let path = temp_path(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn mint() -> TokenMint {
+        TokenMint {
+            address: MINT_ADDRESS,
+            program: spl_token::ID,
+            decimals: 11,
+        }
+    }
+
+    fn payout(row: usize, recipient: &Pubkey, amount: u64) -> PayoutRow {
+        PayoutRow {
+            row,
+            recipient: recipient.to_string(),
+            amount,
+            status: PayoutStatus::Pending,
+            signature: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn amounts_are_parsed_exactly() {
+        assert_eq!(amount_str_to_u64("0.29"), Some(29_000_000_000));
+        assert_eq!(amount_str_to_u64("1"), Some(ORE));
+        assert_eq!(amount_str_to_u64("1."), Some(ORE));
+        assert_eq!(amount_str_to_u64(".5"), Some(ORE / 2));
+        assert_eq!(amount_str_to_u64("0.00000000001"), Some(1));
+        assert_eq!(amount_str_to_u64("0.000000000001"), None);
+        for invalid in ["", ".", "-1", "+1", "1e3", "1.2.3", "NaN", "1,5"] {
+            assert_eq!(amount_str_to_u64(invalid), None, "{}", invalid);
+        }
+        assert_eq!(amount_str_to_u64("999999999999"), None);
+
+        for amount in [0, 1, ORE, 29_000_000_000, 123 * ORE + 4_560_000_000] {
+            let decimal = amount_u64_to_decimal_string(amount);
+            assert_eq!(amount_str_to_u64(&decimal), Some(amount), "{}", decimal);
+        }
+        assert_eq!(amount_u64_to_decimal_string(29_000_000_000), "0.29");
+        assert_eq!(amount_u64_to_decimal_string(ORE), "1");
+    }
+
+    #[test]
+    fn batch_skips_header_blanks_and_comments() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let path = write_batch(
+            "read",
+            &format!("recipient,amount\n\n# payouts\n{}, 0.29\n  \n{},1\n", a, b),
+        );
+        let rows = read_batch(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!((rows[0].row, rows[0].amount), (4, 29_000_000_000));
+        assert_eq!(rows[0].recipient, a.to_string());
+        assert_eq!((rows[1].row, rows[1].amount), (6, ORE));
+        assert!(rows.iter().all(|row| row.status.eq(&PayoutStatus::Pending)));
+    }
+
+    #[test]
+    fn batch_rejects_invalid_rows() {
+        let recipient = Pubkey::new_unique();
+        let cases = [
+            (
+                format!("{},1,2\n", recipient),
+                "expected RECIPIENT_ADDRESS,AMOUNT",
+            ),
+            ("nope,1\n".to_string(), "invalid recipient address"),
+            (format!("{},lots\n", recipient), "invalid amount"),
+            (format!("{},-1\n", recipient), "invalid amount"),
+            (format!("{},0\n", recipient), "amount must be positive"),
+            // Only the first row can be a header
+            (
+                format!("{},1\nfoo,bar\n", recipient),
+                "invalid recipient address",
+            ),
+        ];
+        for (n, (contents, msg)) in cases.iter().enumerate() {
+            let path = write_batch(&format!("invalid-{}", n), contents);
+            let result = read_batch(&path);
+            fs::remove_file(&path).unwrap();
+            match result {
+                Err(Error::Internal(err)) => assert!(err.ends_with(msg), "{}", err),
+                other => panic!("expected {:?} for {:?}, got {:?}", msg, contents, other),
+            }
+        }
+    }
+
+    #[test]
+    fn results_round_trip_into_the_batch() {
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut rows = vec![
+            payout(2, &a, 29_000_000_000),
+            payout(3, &b, ORE),
+            payout(5, &c, 1),
+        ];
+        rows[0].status = PayoutStatus::Confirmed;
+        rows[0].signature = Some("sig".to_string());
+        rows[1].status = PayoutStatus::Failed;
+        rows[1].error = Some("custom \"program\" error, 0x1\nretry".to_string());
+        rows[2].status = PayoutStatus::Unknown;
+
+        let path = temp_path("results");
+        write_results(&path, &rows).unwrap();
+        let results = read_results(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].amount, 29_000_000_000);
+
+        let mut fresh: Vec<PayoutRow> = rows
+            .iter()
+            .map(|row| {
+                payout(
+                    row.row,
+                    &Pubkey::from_str(&row.recipient).unwrap(),
+                    row.amount,
+                )
+            })
+            .collect();
+        merge_results(&mut fresh, &results, &path).unwrap();
+        assert_eq!(fresh[0].status, PayoutStatus::Confirmed);
+        assert_eq!(fresh[0].signature.as_deref(), Some("sig"));
+        assert_eq!(fresh[1].status, PayoutStatus::Failed);
+        assert_eq!(
+            fresh[1].error.as_deref(),
+            Some("custom \"program\" error, 0x1 retry")
+        );
+        assert_eq!(fresh[2].status, PayoutStatus::Unknown);
+        assert_eq!(fresh[2].error, None);
+    }
+
+    #[test]
+    fn results_from_another_batch_are_refused() {
+        let recipient = Pubkey::new_unique();
+        let path = temp_path("mismatch");
+        write_results(&path, &[payout(2, &recipient, ORE)]).unwrap();
+        let results = read_results(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        for mut rows in [
+            vec![payout(2, &recipient, ORE + 1)],
+            vec![payout(2, &Pubkey::new_unique(), ORE)],
+            vec![payout(3, &recipient, ORE)],
+        ] {
+            match merge_results(&mut rows, &results, &path) {
+                Err(Error::Internal(err)) => {
+                    assert!(err.contains("doesn't match the batch at row 2"))
+                }
+                other => panic!("expected a mismatch, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn transfers_pack_within_transaction_limits() {
+        let mint = mint();
+        let (sender, fee_payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let recipients: Vec<Pubkey> = (0..40).map(|_| Pubkey::new_unique()).collect();
+        let rows: Vec<PayoutRow> = recipients
+            .iter()
+            .enumerate()
+            .map(|(i, recipient)| payout(i + 1, recipient, ORE))
+            .collect();
+        let atas: HashMap<Pubkey, Ata> = recipients
+            .iter()
+            .enumerate()
+            .map(|(i, recipient)| {
+                let account = Account::default();
+                let account = Some(&account).filter(|_| i % 2 == 0);
+                (*recipient, Ata::new(&mint, &sender, recipient, account))
+            })
+            .collect();
+        let to_send: Vec<usize> = (0..rows.len()).collect();
+
+        for tip in [false, true] {
+            let chunks = pack(&rows, &to_send, &mint, &atas, &sender, &fee_payer, tip).unwrap();
+            assert!(chunks.len().gt(&1));
+            for chunk in chunks.iter() {
+                assert!(tx_size(&chunk.ixs, &fee_payer, tip).le(&PACKET_DATA_SIZE));
+                assert!(chunk.compute_units.le(&MAX_TX_COMPUTE_UNITS));
+            }
+            let packed: Vec<usize> = chunks.iter().flat_map(|chunk| chunk.rows.clone()).collect();
+            assert_eq!(packed, to_send);
+        }
+    }
+
+    #[test]
+    fn missing_accounts_are_created_once_per_transaction() {
+        let mint = mint();
+        let sender = Pubkey::new_unique();
+        let (missing, existing) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let account = Account::default();
+        let atas = HashMap::from([
+            (missing, Ata::new(&mint, &sender, &missing, None)),
+            (
+                existing,
+                Ata::new(&mint, &sender, &existing, Some(&account)),
+            ),
+        ]);
+        let rows = vec![
+            payout(1, &missing, ORE),
+            payout(2, &existing, ORE),
+            payout(3, &missing, 2 * ORE),
+        ];
+        let chunks = pack(&rows, &[0, 1, 2], &mint, &atas, &sender, &sender, false).unwrap();
+        assert_eq!(chunks.len(), 1);
+        let chunk = &chunks[0];
+        let creates = chunk
+            .ixs
+            .iter()
+            .filter(|ix| ix.program_id.eq(&spl_associated_token_account::ID))
+            .count();
+        assert_eq!(creates, 1);
+        assert_eq!(chunk.ixs.len(), 4);
+        assert_eq!(
+            chunk.compute_units,
+            3 * CU_LIMIT_TOKEN_TRANSFER + atas[&missing].compute_units()
+        );
+        assert_eq!(chunk.created, HashSet::from([missing]));
+    }
+}
@@ -1,141 +1,175 @@
-// use std::str::FromStr;
+use std::str::FromStr;
 
-// use ore_boost_api::state::{boost_pda, stake_pda, Boost, Stake};
-// use solana_program::{program_pack::Pack, pubkey::Pubkey};
-// use solana_sdk::{signature::Signer, transaction::Transaction};
-// use spl_token::state::Mint;
-// use steel::AccountDeserialize;
+use ore_boost_api::state::{boost_pda, stake_pda, Boost, Stake};
+use serde::Serialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_sdk::signature::Signer;
+use spl_token::amount_to_ui_amount;
+use steel::AccountDeserialize;
 
-// use crate::{
-//     args::UnstakeArgs, cu_limits::CU_LIMIT_CLAIM, error::Error, pool::Pool,
-//     send_and_confirm::ComputeBudget, Miner,
-// };
+use crate::{
+    args::UnstakeArgs,
+    cu_limits::CU_LIMIT_CLAIM,
+    error::Error,
+    output::{self, TxOutcome},
+    pool::Pool,
+    send_and_confirm::ComputeBudget,
+    token_account::TokenMint,
+    Miner,
+};
 
-// impl Miner {
-//     pub async fn unstake(&self, args: UnstakeArgs) {
-//         match args.pool_url {
-//             None => {
-//                 self.unstake_solo(&args).await;
-//             }
-//             Some(ref pool_url) => {
-//                 if let Err(err) = self.unstake_pool(&args, pool_url).await {
-//                     println!("{:?}", err);
-//                 }
-//             }
-//         }
-//     }
-//     async fn unstake_pool(&self, args: &UnstakeArgs, pool_url: &String) -> Result<(), Error> {
-//         let signer = self.signer();
-//         // build pool client
-//         let pool = Pool {
-//             http_client: reqwest::Client::new(),
-//             pool_url: pool_url.clone(),
-//         };
-//         // parse mint
-//         let mint_address = Pubkey::from_str(args.mint.as_str())?;
-//         // get beneficiary
-//         let beneficiary = match &args.token_account {
-//             Some(address) => Pubkey::from_str(&address)?,
-//             None => spl_associated_token_account::get_associated_token_address(
-//                 &signer.pubkey(),
-//                 &mint_address,
-//             ),
-//         };
-//         // assert that token account exists
-//         let Ok(Some(_token_account)) = self.rpc_client.get_token_account(&beneficiary).await else {
-//             return Err(Error::Internal("failed to fetch token account".to_string()));
-//         };
-//         // fetch mint account
-//         let mint_data = self.rpc_client.get_account_data(&mint_address).await?;
-//         let mint = Mint::unpack(&mint_data)?;
-//         // assert that boost account exists
-//         let boost_address = boost_pda(mint_address).0;
-//         let boost_account_data = self.rpc_client.get_account_data(&boost_address).await?;
-//         let _boost = Boost::try_from_bytes(boost_account_data.as_slice())?;
-//         // fetch share account
-//         let pool_address = pool.get_pool_address().await?;
-//         let share = pool
-//             .get_staker_onchain(self, pool_address.address, mint_address)
-//             .await?;
-//         // parse amount
-//         let amount: u64 = if let Some(amount) = args.amount {
-//             (amount * 10f64.powf(mint.decimals as f64)) as u64
-//         } else {
-//             share.balance
-//         };
-//         // send tx
-//         let ix = ore_pool_api::sdk::unstake(
-//             signer.pubkey(),
-//             mint_address,
-//             pool_address.address,
-//             beneficiary,
-//             amount,
-//         );
-//         let mut tx = Transaction::new_with_payer(&[ix], Some(&signer.pubkey()));
-//         let hash = self.rpc_client.get_latest_blockhash().await?;
-//         tx.sign(&[&signer], hash);
-//         let sig = self.rpc_client.send_transaction(&tx).await?;
-//         println!("{:?}", sig);
-//         Ok(())
-//     }
-//     async fn unstake_solo(&self, args: &UnstakeArgs) {
-//         // Parse mint address
-//         let mint_address = Pubkey::from_str(&args.mint).unwrap();
+/// The outcome of an unstake, with the balances after it. Amounts are in the mint's
+/// base units.
+#[derive(Clone, Debug, Serialize)]
+pub struct UnstakeOutput {
+    pub mint: String,
+    pub beneficiary: String,
+    pub amount: u64,
+    /// What is left in the stake account, or in the pool share when unstaking from a pool.
+    pub staked: Option<u64>,
+    /// The beneficiary token account's balance.
+    pub beneficiary_balance: Option<u64>,
+    #[serde(skip)]
+    pub decimals: u8,
+    #[serde(flatten)]
+    pub tx: TxOutcome,
+}
 
-//         // Get signer
-//         let signer = self.signer();
-//         let beneficiary = match &args.token_account {
-//             Some(address) => {
-//                 Pubkey::from_str(&address).expect("Failed to parse token account address")
-//             }
-//             None => spl_associated_token_account::get_associated_token_address(
-//                 &signer.pubkey(),
-//                 &mint_address,
-//             ),
-//         };
+impl Miner {
+    pub async fn unstake(&self, args: UnstakeArgs) {
+        match self.unstake_tokens(&args).await {
+            Ok(unstake) => output::print(&unstake, |unstake| {
+                let ui_amount = |amount: Option<u64>| {
+                    amount
+                        .map(|amount| amount_to_ui_amount(amount, unstake.decimals).to_string())
+                        .unwrap_or("unknown".to_string())
+                };
+                if unstake.tx.signature.is_some() {
+                    println!("Staked: {}", ui_amount(unstake.staked));
+                    println!(
+                        "Beneficiary {}: {}",
+                        unstake.beneficiary,
+                        ui_amount(unstake.beneficiary_balance)
+                    );
+                }
+            }),
+            Err(err) => output::print_error(err),
+        }
+    }
 
-//         // Get token account
-//         let Ok(Some(_token_account)) = self.rpc_client.get_token_account(&beneficiary).await else {
-//             println!("Failed to fetch token account");
-//             return;
-//         };
+    /// Withdraws stake of `args.mint` to the beneficiary token account, from the signer's
+    /// stake account or, given a pool url, from the signer's share of the pool stake.
+    pub async fn unstake_tokens(&self, args: &UnstakeArgs) -> Result<UnstakeOutput, Error> {
+        let signer = self.signer();
+        let mint_address = Pubkey::from_str(&args.mint)?;
 
-//         let Ok(mint_data) = self.rpc_client.get_account_data(&mint_address).await else {
-//             println!("Failed to fetch mint address");
-//             return;
-//         };
-//         let mint = Mint::unpack(&mint_data).unwrap();
+        // Assert that the mint exists
+        let mint = TokenMint::fetch(&self.rpc_client, mint_address).await?;
 
-//         // Get addresses
-//         let boost_address = boost_pda(mint_address).0;
-//         let stake_address = stake_pda(signer.pubkey(), boost_address).0;
+        // Assert that the beneficiary token account exists
+        let beneficiary = match &args.token_account {
+            Some(address) => Pubkey::from_str(address)?,
+            None => mint.ata(&signer.pubkey()),
+        };
+        let Ok(Some(_token_account)) = self.rpc_client.get_token_account(&beneficiary).await else {
+            return Err(Error::Internal("failed to fetch token account".to_string()));
+        };
 
-//         // Fetch boost
-//         let Ok(boost_account_data) = self.rpc_client.get_account_data(&boost_address).await else {
-//             println!("Failed to fetch boost account");
-//             return;
-//         };
-//         let _ = Boost::try_from_bytes(&boost_account_data).unwrap();
+        // Assert that the boost exists
+        let boost_address = boost_pda(mint_address).0;
+        let boost_data = self.rpc_client.get_account_data(&boost_address).await?;
+        let _ = Boost::try_from_bytes(&boost_data)?;
 
-//         // Fetch stake account, if needed
-//         let Ok(stake_account_data) = self.rpc_client.get_account_data(&stake_address).await else {
-//             println!("Failed to fetch stake account");
-//             return;
-//         };
-//         let stake = Stake::try_from_bytes(&stake_account_data).unwrap();
+        // Build the withdrawal from the stake account or pool share
+        let pool = args.pool_url.clone().map(|pool_url| Pool {
+            http_client: reqwest::Client::new(),
+            pool_url,
+        });
+        let pool_address = match &pool {
+            Some(pool) => Some(pool.get_pool_address().await?.address),
+            None => None,
+        };
+        let staked = self
+            .get_staked(mint_address, boost_address, pool.as_ref(), pool_address)
+            .await?;
+        let amount: u64 = if let Some(amount) = args.amount {
+            (amount * 10f64.powf(mint.decimals as f64)) as u64
+        } else {
+            staked
+        };
+        let ix = match pool_address {
+            None => mint.with_program(
+                withdraw_to(signer.pubkey(), mint_address, beneficiary, amount),
+                &[boost_address],
+            ),
+            Some(pool_address) => mint.with_program(
+                ore_pool_api::sdk::unstake(
+                    signer.pubkey(),
+                    mint_address,
+                    pool_address,
+                    beneficiary,
+                    amount,
+                ),
+                &[pool_address, boost_address],
+            ),
+        };
 
-//         // Parse amount
-//         let amount: u64 = if let Some(amount) = args.amount {
-//             (amount * 10f64.powf(mint.decimals as f64)) as u64
-//         } else {
-//             stake.balance
-//         };
+        // Send tx
+        let result = self
+            .send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
+            .await;
 
-//         // Send tx
-//         // TODO: benfeciary should be arg to ix builder
-//         let ix = ore_boost_api::sdk::withdraw(signer.pubkey(), mint_address, amount);
-//         self.send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false)
-//             .await
-//             .ok();
-//     }
-// }
+        // Fetch the balances left after it
+        let staked = self
+            .get_staked(mint_address, boost_address, pool.as_ref(), pool_address)
+            .await
+            .ok();
+        let beneficiary_balance = match self.rpc_client.get_token_account(&beneficiary).await {
+            Ok(Some(token_account)) => u64::from_str(&token_account.token_amount.amount).ok(),
+            _ => None,
+        };
+        Ok(UnstakeOutput {
+            mint: mint_address.to_string(),
+            beneficiary: beneficiary.to_string(),
+            amount,
+            staked,
+            beneficiary_balance,
+            decimals: mint.decimals,
+            tx: TxOutcome::new(&result),
+        })
+    }
+
+    /// The signer's stake in the boost, held directly or as a share of the pool stake.
+    async fn get_staked(
+        &self,
+        mint_address: Pubkey,
+        boost_address: Pubkey,
+        pool: Option<&Pool>,
+        pool_address: Option<Pubkey>,
+    ) -> Result<u64, Error> {
+        match (pool, pool_address) {
+            (Some(pool), Some(pool_address)) => {
+                let share = pool
+                    .get_staker_onchain(self, pool_address, mint_address)
+                    .await?;
+                Ok(share.balance)
+            }
+            _ => {
+                let stake_address = stake_pda(self.signer().pubkey(), boost_address).0;
+                let stake_data = self.rpc_client.get_account_data(&stake_address).await?;
+                Ok(Stake::try_from_bytes(&stake_data)?.balance)
+            }
+        }
+    }
+}
+
+/// A boost withdrawal paying out to `beneficiary`. The SDK builder always pays out to the
+/// signer's associated token account, so its beneficiary account is swapped out.
+fn withdraw_to(signer: Pubkey, mint: Pubkey, beneficiary: Pubkey, amount: u64) -> Instruction {
+    let mut ix = ore_boost_api::sdk::withdraw(signer, mint, amount);
+    ix.accounts[1] = AccountMeta::new(beneficiary, false);
+    ix
+}
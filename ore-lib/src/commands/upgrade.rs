@@ -1,4 +1,5 @@
 use colored::*;
+use serde::Serialize;
 use solana_sdk::{pubkey::Pubkey, signer::Signer};
 use spl_token::amount_to_ui_amount;
 
@@ -6,48 +7,82 @@ use crate::{
     args::UpgradeArgs,
     cu_limits::CU_LIMIT_UPGRADE,
     miner::Miner,
+    output::{self, status, TxOutcome},
     send_and_confirm::ComputeBudget,
-    utils::{amount_f64_to_u64_v1, ask_confirm},
+    utils::{amount_f64_to_u64_v1},
 };
 
+/// The outcome of upgrading v1 tokens to v2.
+#[derive(Clone, Debug, Serialize)]
+pub struct UpgradeOutput {
+    /// In v1 grains.
+    pub amount: u64,
+    #[serde(flatten)]
+    pub tx: TxOutcome,
+}
+
 impl Miner {
     pub async fn upgrade(&self, args: UpgradeArgs) {
+        let upgrade = self.upgrade_tokens(args).await;
+        output::print(&upgrade, |upgrade| {
+            if let Some(err) = &upgrade.tx.error {
+                println!("error: {}", err);
+            }
+        });
+    }
+
+    async fn upgrade_tokens(&self, args: UpgradeArgs) -> UpgradeOutput {
         let signer = &self.signer();
-        let beneficiary = self.get_or_initialize_ata().await;
         let (sender, sender_balance) = self.get_ata_v1().await;
 
         let amount_f64 = match args.amount {
             Some(f64) => f64,
             None => {
-                println!(
+                status(format!(
                     "Defaulting to max amount of v1 Ore token in wallet: {}",
                     sender_balance
-                );
+                ));
                 sender_balance
             }
         };
         let amount = amount_f64_to_u64_v1(amount_f64);
         let amount_ui = amount_to_ui_amount(amount, ore_api::consts::TOKEN_DECIMALS_V1);
 
-        if !ask_confirm(
+        if !self.confirmer.confirm(
             format!(
                 "\n You are about to upgrade {}. \n\nAre you sure you want to continue? [Y/n]",
                 format!("{} ORE", amount_ui).bold(),
             )
             .as_str(),
         ) {
-            return;
+            return UpgradeOutput {
+                amount,
+                tx: TxOutcome::cancelled(),
+            };
         }
 
-        let ix = ore_api::sdk::upgrade(signer.pubkey(), beneficiary, sender, amount);
-        match self
-            .send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_UPGRADE), false)
-            .await
-        {
-            Ok(_sig) => {}
+        // Create the v2 token account, if needed
+        let ata = match self.get_ata(ore_api::consts::MINT_ADDRESS, &signer.pubkey()).await {
+            Ok(ata) => ata,
             Err(err) => {
-                println!("error: {}", err);
+                return UpgradeOutput {
+                    amount,
+                    tx: TxOutcome::failed(format!("{:?}", err)),
+                }
             }
+        };
+        let mut ixs: Vec<_> = ata.create_ix.clone().into_iter().collect();
+        ixs.push(ore_api::sdk::upgrade(signer.pubkey(), ata.address, sender, amount));
+        let result = self
+            .send_and_confirm(
+                &ixs,
+                ComputeBudget::Fixed(CU_LIMIT_UPGRADE + ata.compute_units()),
+                false,
+            )
+            .await;
+        UpgradeOutput {
+            amount,
+            tx: TxOutcome::new(&result),
         }
     }
 
@@ -85,33 +120,4 @@ impl Miner {
         // Return v1 token account address
         (token_account_pubkey_v1, balance)
     }
-
-    async fn get_or_initialize_ata(&self) -> Pubkey {
-        // Initialize client
-        let signer = self.signer();
-        let client = self.rpc_client.clone();
-
-        // Derive assoicated token address (ata)
-        let token_account_pubkey = spl_associated_token_account::get_associated_token_address(
-            &signer.pubkey(),
-            &ore_api::consts::MINT_ADDRESS,
-        );
-
-        // Check if ata already exists or init
-        if let Err(_err) = client.get_token_account(&token_account_pubkey).await {
-            println!("Initializing v2 token account...");
-            let ix = spl_associated_token_account::instruction::create_associated_token_account(
-                &signer.pubkey(),
-                &signer.pubkey(),
-                &ore_api::consts::MINT_ADDRESS,
-                &spl_token::id(),
-            );
-            self.send_and_confirm(&[ix], ComputeBudget::Fixed(500_000), false)
-                .await
-                .ok();
-        }
-
-        // Return token account address
-        token_account_pubkey
-    }
 }
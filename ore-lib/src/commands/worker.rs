@@ -0,0 +1,20 @@
+use crate::{
+    args::WorkerArgs, core_alloc::CoreAllocation, error::Error, hash_backend::CpuBackend, remote,
+    throttle::Throttle, Miner,
+};
+
+impl Miner {
+    pub async fn worker(&self, args: WorkerArgs) -> Result<(), Error> {
+        // Check num threads
+        self.check_num_cores(args.cores);
+
+        // Serve coordinators until the process is stopped
+        let backend = self.backend(CpuBackend::new(
+            CoreAllocation::new(&args.core_list, args.pin),
+            Throttle::default(),
+        ));
+        tokio::task::spawn_blocking(move || remote::serve(&args.bind, args.cores, backend))
+            .await
+            .map_err(|err| Error::Internal(format!("worker failed: {}", err)))?
+    }
+}
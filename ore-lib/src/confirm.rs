@@ -0,0 +1,43 @@
+use std::io::BufRead;
+
+use crate::output::status;
+
+/// Decides whether a command goes ahead with an action the user should approve,
+/// like sending tokens. Embedders supply their own to show a confirmation UI.
+pub trait Confirmer: Send + Sync {
+    fn confirm(&self, question: &str) -> bool;
+}
+
+/// Approves everything, as with `--yes`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AutoYes;
+
+impl Confirmer for AutoYes {
+    fn confirm(&self, _question: &str) -> bool {
+        true
+    }
+}
+
+/// Asks on the terminal and reads a y/n answer from stdin. Declines if stdin is closed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TerminalConfirmer;
+
+impl Confirmer for TerminalConfirmer {
+    fn confirm(&self, question: &str) -> bool {
+        status(question);
+        let stdin = std::io::stdin();
+        loop {
+            let mut input = String::new();
+            match stdin.lock().read_line(&mut input) {
+                Ok(0) | Err(_) => return false,
+                Ok(_) => {}
+            }
+            match input.trim().to_lowercase().as_str() {
+                "y" | "yes" => return true,
+                "n" | "no" => return false,
+                _ => status("y/n only please."),
+            }
+        }
+    }
+}
+
@@ -0,0 +1,113 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+    Arc,
+};
+
+use clap::ValueEnum;
+
+/// How a running miner winds down once it is asked to stop.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[repr(u8)]
+pub enum CancelMode {
+    /// Abort hashing now and drop the current round.
+    #[default]
+    Immediate,
+
+    /// Keep hashing until the cutoff, submit the solution, then stop.
+    AfterSubmit,
+
+    /// Stop hashing now and submit the best solution so far, if it meets the min difficulty.
+    SubmitBestNow,
+}
+
+impl From<u8> for CancelMode {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => CancelMode::AfterSubmit,
+            2 => CancelMode::SubmitBestNow,
+            _ => CancelMode::Immediate,
+        }
+    }
+}
+
+/// Shared handle used to stop a running mining loop from another task or thread.
+#[derive(Clone, Debug)]
+pub struct MiningControl {
+    is_mining: Arc<AtomicBool>,
+    cancel_mode: Arc<AtomicU8>,
+    cores: Arc<AtomicU64>,
+    cores_epoch: Arc<AtomicU64>,
+}
+
+impl Default for MiningControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MiningControl {
+    pub fn new() -> Self {
+        Self {
+            is_mining: Arc::new(AtomicBool::new(false)),
+            cancel_mode: Arc::new(AtomicU8::new(CancelMode::Immediate as u8)),
+            cores: Arc::new(AtomicU64::new(0)),
+            cores_epoch: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn start(&self) {
+        self.cancel_mode
+            .store(CancelMode::Immediate as u8, Ordering::SeqCst);
+        self.is_mining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn stop(&self, mode: CancelMode) {
+        self.cancel_mode.store(mode as u8, Ordering::SeqCst);
+        self.is_mining.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_mining(&self) -> bool {
+        self.is_mining.load(Ordering::SeqCst)
+    }
+
+    pub fn cancel_mode(&self) -> CancelMode {
+        CancelMode::from(self.cancel_mode.load(Ordering::SeqCst))
+    }
+
+    /// Returns true if hashing threads should drop what they are doing.
+    pub fn should_abort_hashing(&self) -> bool {
+        !self.is_mining() && self.cancel_mode() != CancelMode::AfterSubmit
+    }
+
+    /// Returns true if a solution with the given difficulty should still be submitted,
+    /// taking a pending stop request into account.
+    pub fn should_submit(&self, difficulty: u32, min_difficulty: u32) -> bool {
+        if self.is_mining() {
+            return true;
+        }
+        match self.cancel_mode() {
+            CancelMode::Immediate => false,
+            CancelMode::AfterSubmit => true,
+            CancelMode::SubmitBestNow => difficulty.ge(&min_difficulty),
+        }
+    }
+
+    /// Overrides the number of hashing threads. The new count is picked up at the next
+    /// round, or right away if `immediate`, re-splitting the rest of the current round.
+    pub fn set_cores(&self, cores: u64, immediate: bool) {
+        self.cores.store(cores, Ordering::SeqCst);
+        if immediate {
+            self.cores_epoch.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// The core count set with `set_cores`, if any.
+    pub fn cores(&self) -> Option<u64> {
+        Some(self.cores.load(Ordering::SeqCst)).filter(|cores| cores.gt(&0))
+    }
+
+    /// Bumped on every immediate core count change.
+    pub fn cores_epoch(&self) -> u64 {
+        self.cores_epoch.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeSet;
+
+use clap::ValueEnum;
+use colored::*;
+use core_affinity::CoreId;
+
+use crate::output::status;
+
+/// How mining threads are pinned to CPU cores.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum PinMode {
+    /// Pin to one hardware thread per physical core first, then to their SMT siblings.
+    #[default]
+    Physical,
+
+    /// Pin to cores in the order the OS lists them.
+    Logical,
+
+    /// Do not pin threads; let the OS schedule them.
+    None,
+}
+
+/// The cores that mining threads are pinned to, in the order threads are assigned to them.
+/// Thread `i` runs on core `i % len`, independent of the OS core IDs.
+#[derive(Clone, Debug)]
+pub struct CoreAllocation {
+    cores: Vec<CoreId>,
+}
+
+impl Default for CoreAllocation {
+    fn default() -> Self {
+        Self::new(&[], PinMode::default())
+    }
+}
+
+impl CoreAllocation {
+    /// Builds an allocation from an explicit core list, or from all available cores if empty.
+    pub fn new(core_list: &[usize], pin: PinMode) -> Self {
+        if pin.eq(&PinMode::None) {
+            return Self { cores: vec![] };
+        }
+        let available = core_affinity::get_core_ids().unwrap_or_default();
+
+        // Honor an explicit core list, skipping cores the OS does not expose
+        if !core_list.is_empty() {
+            let cores = core_list
+                .iter()
+                .filter_map(|id| {
+                    let core = available.iter().find(|core| core.id.eq(id)).copied();
+                    if core.is_none() {
+                        status(format!(
+                            "{} Core {} is not available",
+                            "WARNING".bold().yellow(),
+                            id
+                        ));
+                    }
+                    core
+                })
+                .collect();
+            return Self { cores };
+        }
+
+        // Otherwise order the available cores
+        let mut cores = available;
+        cores.sort_by_key(|core| core.id);
+        if pin.eq(&PinMode::Physical) {
+            cores = physical_first(cores);
+        }
+        Self { cores }
+    }
+
+    /// The core thread `thread` should be pinned to, if any.
+    pub fn core_for(&self, thread: usize) -> Option<CoreId> {
+        if self.cores.is_empty() {
+            return None;
+        }
+        self.cores.get(thread % self.cores.len()).copied()
+    }
+}
+
+/// Reorders cores so the first hardware thread of every physical core comes before any
+/// SMT sibling. Falls back to the given order if the topology cannot be read.
+fn physical_first(cores: Vec<CoreId>) -> Vec<CoreId> {
+    let mut seen = BTreeSet::new();
+    let (mut primary, mut siblings) = (vec![], vec![]);
+    for core in cores {
+        let group = thread_siblings(core.id).unwrap_or_else(|| vec![core.id]);
+        let first = group.iter().min().copied().unwrap_or(core.id);
+        if seen.insert(first) {
+            primary.push(core);
+        } else {
+            siblings.push(core);
+        }
+    }
+    primary.extend(siblings);
+    primary
+}
+
+/// The hardware threads sharing a physical core with `cpu`, as reported by sysfs.
+fn thread_siblings(cpu: usize) -> Option<Vec<usize>> {
+    let path = format!(
+        "/sys/devices/system/cpu/cpu{}/topology/thread_siblings_list",
+        cpu
+    );
+    parse_cpu_list(std::fs::read_to_string(path).ok()?.trim())
+}
+
+/// Parses a kernel cpu list such as `0-3,8,10-11`, or `None` if it's malformed.
+fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = vec![];
+    for part in list.split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (start.parse::<usize>().ok()?, end.parse::<usize>().ok()?);
+                if start.gt(&end) {
+                    return None;
+                }
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(part.parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11"), Some(vec![0, 1, 2, 3, 8, 10, 11]));
+        assert_eq!(parse_cpu_list("5"), Some(vec![5]));
+        assert_eq!(parse_cpu_list("2-2"), Some(vec![2]));
+        assert_eq!(parse_cpu_list("1,"), Some(vec![1]));
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+    }
+
+    #[test]
+    fn rejects_malformed_cpu_lists() {
+        assert_eq!(parse_cpu_list("3-1"), None);
+        assert_eq!(parse_cpu_list("a,b"), None);
+        assert_eq!(parse_cpu_list("0-"), None);
+        assert_eq!(parse_cpu_list("-2"), None);
+        assert_eq!(parse_cpu_list("0-1-2"), None);
+        assert_eq!(parse_cpu_list("1, 2"), None);
+    }
+
+    #[test]
+    fn threads_wrap_around_the_allocation() {
+        let allocation = CoreAllocation {
+            cores: vec![CoreId { id: 4 }, CoreId { id: 6 }],
+        };
+        assert_eq!(allocation.core_for(0).map(|core| core.id), Some(4));
+        assert_eq!(allocation.core_for(1).map(|core| core.id), Some(6));
+        assert_eq!(allocation.core_for(2).map(|core| core.id), Some(4));
+        assert!(CoreAllocation::new(&[], PinMode::None).core_for(0).is_none());
+    }
+}
@@ -2,3 +2,7 @@ pub const CU_LIMIT_UPGRADE: u32 = 20_000;
 pub const CU_LIMIT_CLAIM: u32 = 32_000;
 pub const _CU_LIMIT_RESET: u32 = 12_200;
 pub const _CU_LIMIT_MINE: u32 = 3200;
+pub const CU_LIMIT_MINE_TX: u32 = 600_000;
+pub const CU_LIMIT_TOKEN_TRANSFER: u32 = 6_000;
+pub const CU_LIMIT_CREATE_ATA: u32 = 30_000;
+pub const CU_LIMIT_MEMO: u32 = 10_000;
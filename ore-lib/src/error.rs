@@ -12,6 +12,14 @@ pub enum Error {
     SolanaProgram(#[from] solana_program::program_error::ProgramError),
     #[error("parse int")]
     ParseInt(#[from] std::num::ParseIntError),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("profile not found: {0}")]
+    ProfileNotFound(String),
+    #[error("invalid boost {0}: {1}")]
+    InvalidBoost(String, String),
     #[error("already mining")]
     AlreadyMining,
     #[error("not mining")]
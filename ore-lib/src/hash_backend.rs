@@ -0,0 +1,510 @@
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+
+use drillx::{equix, Hash, Solution};
+use ore_api::state::Config;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    args::MineArgs,
+    control::MiningControl,
+    core_alloc::CoreAllocation,
+    error::Error,
+    throttle::{Throttle, Throttler},
+    utils::amount_f64_to_u64,
+};
+
+/// A unit of hashing work: one challenge, and one nonce range per worker thread.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HashJob {
+    pub challenge: [u8; 32],
+    pub nonce_ranges: Vec<Range<u64>>,
+    pub policy: StopPolicy,
+}
+
+/// Progress shared between a running backend and its caller.
+#[derive(Debug)]
+pub struct HashProgress {
+    best_difficulty: AtomicU32,
+    hashes: AtomicU64,
+    histogram: Vec<AtomicU64>,
+    thread_hashes: Mutex<Vec<u64>>,
+}
+
+impl Default for HashProgress {
+    fn default() -> Self {
+        HashProgress {
+            best_difficulty: AtomicU32::new(0),
+            hashes: AtomicU64::new(0),
+            histogram: (0..=256).map(|_| AtomicU64::new(0)).collect(),
+            thread_hashes: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl HashProgress {
+    pub fn best_difficulty(&self) -> u32 {
+        self.best_difficulty.load(Ordering::Relaxed)
+    }
+
+    pub fn hashes(&self) -> u64 {
+        self.hashes.load(Ordering::Relaxed)
+    }
+
+    pub fn record_difficulty(&self, difficulty: u32) {
+        self.best_difficulty
+            .fetch_max(difficulty, Ordering::Relaxed);
+    }
+
+    pub fn add_hashes(&self, count: u64) {
+        self.hashes.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Counts one hash of the given difficulty in the histogram.
+    pub fn record_hash(&self, difficulty: u32) {
+        if let Some(count) = self.histogram.get(difficulty as usize) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The number of hashes found per difficulty, skipping difficulties never seen.
+    pub fn histogram(&self) -> Vec<(u32, u64)> {
+        self.histogram
+            .iter()
+            .enumerate()
+            .map(|(difficulty, count)| (difficulty as u32, count.load(Ordering::Relaxed)))
+            .filter(|(_, count)| count.gt(&0))
+            .collect()
+    }
+
+    /// Adds to the hash count of a single thread, for backends that track them.
+    pub fn add_thread_hashes(&self, thread: usize, count: u64) {
+        let mut thread_hashes = self.thread_hashes.lock().unwrap();
+        if thread_hashes.len().le(&thread) {
+            thread_hashes.resize(thread + 1, 0);
+        }
+        thread_hashes[thread] += count;
+    }
+
+    /// The hash count of each thread, if the backend tracks them.
+    pub fn thread_hashes(&self) -> Vec<u64> {
+        self.thread_hashes.lock().unwrap().clone()
+    }
+}
+
+/// The best solution a backend found for a job.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct HashResult {
+    pub solution: Solution,
+    pub difficulty: u32,
+    pub hashes: u64,
+}
+
+impl Default for HashResult {
+    fn default() -> Self {
+        HashResult {
+            solution: Solution::new([0; 16], [0; 8]),
+            difficulty: 0,
+            hashes: 0,
+        }
+    }
+}
+
+impl HashResult {
+    /// Keeps whichever of the two results has the higher difficulty, summing the hash counts.
+    pub fn merge(self, other: HashResult) -> HashResult {
+        let hashes = self.hashes.saturating_add(other.hashes);
+        let best = if other.difficulty.gt(&self.difficulty) {
+            other
+        } else {
+            self
+        };
+        HashResult { hashes, ..best }
+    }
+}
+
+/// Something that can search nonce ranges for drillx solutions.
+pub trait HashBackend: Send + Sync {
+    /// Called once per job before hashing starts, e.g. to hand the challenge to a device.
+    fn prepare(&self, _challenge: &[u8; 32]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Hashes the job until its stop policy is met or the control aborts.
+    /// This blocks the calling thread and reports progress as it goes.
+    fn run(&self, job: &HashJob, progress: &HashProgress, control: &MiningControl) -> HashResult;
+}
+
+/// The default backend: one drillx thread per nonce range, pinned per its core allocation
+/// and slowed down per its throttle.
+#[derive(Clone, Debug, Default)]
+pub struct CpuBackend {
+    pub cores: CoreAllocation,
+    pub throttle: Throttle,
+}
+
+impl CpuBackend {
+    pub fn new(cores: CoreAllocation, throttle: Throttle) -> Self {
+        Self { cores, throttle }
+    }
+}
+
+impl HashBackend for CpuBackend {
+    fn run(&self, job: &HashJob, progress: &HashProgress, control: &MiningControl) -> HashResult {
+        let started_at = Instant::now();
+        let mut nonce_ranges = job.nonce_ranges.clone();
+        let mut result = HashResult::default();
+        loop {
+            let epoch = control.cores_epoch();
+            result = std::thread::scope(|scope| {
+                let handles: Vec<_> = nonce_ranges
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(i, nonce_range)| {
+                        let core = self.cores.core_for(i);
+                        scope.spawn(move || {
+                            // Pin to core
+                            if let Some(core) = core {
+                                let _ = core_affinity::set_for_current(core);
+                            }
+                            hash_range(
+                                job,
+                                nonce_range,
+                                started_at,
+                                epoch,
+                                &self.throttle,
+                                progress,
+                                control,
+                            )
+                        })
+                    })
+                    .collect();
+
+                // Join handles and return the best result
+                handles
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, h)| h.join().ok().map(|thread_result| (i, thread_result)))
+                    .fold(result, |result, (i, thread_result)| {
+                        progress.add_thread_hashes(i, thread_result.hashes);
+                        result.merge(thread_result)
+                    })
+            });
+
+            // Re-split what is left of the ranges if the core count changed mid-round
+            let elapsed = started_at.elapsed().as_secs();
+            if control.cores_epoch().eq(&epoch)
+                || control.should_abort_hashing()
+                || job.policy.is_done(elapsed, progress.best_difficulty())
+            {
+                return result;
+            }
+            let cores = control.cores().unwrap_or(nonce_ranges.len() as u64);
+            nonce_ranges = resplit_ranges(nonce_ranges, cores as usize);
+        }
+    }
+}
+
+/// Reshapes leftover nonce ranges into `count` ranges, splitting the largest ones to add
+/// threads and dropping the smallest ones to retire threads.
+pub fn resplit_ranges(mut ranges: Vec<Range<u64>>, count: usize) -> Vec<Range<u64>> {
+    let count = count.max(1);
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_by_key(|range| std::cmp::Reverse(range.end - range.start));
+    ranges.truncate(count);
+    while ranges.len().lt(&count) {
+        let Some(largest) = ranges
+            .first()
+            .cloned()
+            .filter(|range| range.end - range.start > 1)
+        else {
+            break;
+        };
+        let halves = split_range(&largest, 2);
+        ranges.splice(0..1, halves);
+        ranges.sort_by_key(|range| std::cmp::Reverse(range.end - range.start));
+    }
+    ranges
+}
+
+/// Hashes a single nonce range on the current thread, timing the stop policy from
+/// `started_at` and stopping once the core count moves past `epoch`. On return,
+/// `nonce_range` holds the part that was not searched.
+pub fn hash_range(
+    job: &HashJob,
+    nonce_range: &mut Range<u64>,
+    started_at: Instant,
+    epoch: u64,
+    throttle: &Throttle,
+    progress: &HashProgress,
+    control: &MiningControl,
+) -> HashResult {
+    let mut throttler = Throttler::new(throttle);
+    let mut memory = equix::SolverMemory::new();
+    let mut nonce = nonce_range.start;
+    let mut best_nonce = nonce;
+    let mut best_difficulty = 0;
+    let mut best_hash = Hash::default();
+    let mut hashes = 0u64;
+    while nonce.lt(&nonce_range.end) {
+        if control.should_abort_hashing() || control.cores_epoch().ne(&epoch) {
+            break;
+        }
+
+        // Get hashes
+        let hxs = drillx::hashes_with_memory(&mut memory, &job.challenge, &nonce.to_le_bytes());
+        hashes += hxs.len() as u64;
+        progress.add_hashes(hxs.len() as u64);
+
+        // Look for best difficulty score in all hashes
+        for hx in hxs {
+            let difficulty = hx.difficulty();
+            progress.record_hash(difficulty);
+            if difficulty.gt(&best_difficulty) {
+                best_nonce = nonce;
+                best_difficulty = difficulty;
+                best_hash = hx;
+                progress.record_difficulty(best_difficulty);
+            }
+        }
+
+        // Exit if the target was hit or time has elapsed, otherwise rest if throttled
+        if nonce % 100 == 0 {
+            let is_done = || {
+                control.should_abort_hashing()
+                    || control.cores_epoch().ne(&epoch)
+                    || job
+                        .policy
+                        .is_done(started_at.elapsed().as_secs(), progress.best_difficulty())
+            };
+            if is_done() {
+                break;
+            }
+            throttler.pause(is_done);
+        }
+
+        // Increment nonce
+        nonce += 1;
+    }
+    nonce_range.start = nonce;
+
+    HashResult {
+        solution: Solution::new(best_hash.d, best_nonce.to_le_bytes()),
+        difficulty: best_difficulty,
+        hashes,
+    }
+}
+
+/// When the hashing threads should stop and hand back their best solution.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct StopPolicy {
+    pub cutoff_time: u64,
+    pub min_difficulty: u32,
+    pub target_difficulty: Option<u32>,
+    pub extend_floor: Option<u32>,
+    pub extend_limit: Option<u64>,
+}
+
+impl StopPolicy {
+    pub fn new(
+        args: &MineArgs,
+        config: Option<&Config>,
+        cutoff_time: u64,
+        min_difficulty: u32,
+    ) -> Self {
+        let target_difficulty = args.target_difficulty.or_else(|| {
+            let (reward, config) = (args.target_reward?, config?);
+            Some(difficulty_for_reward(
+                config,
+                amount_f64_to_u64(reward),
+                min_difficulty,
+            ))
+        });
+        StopPolicy {
+            cutoff_time,
+            min_difficulty,
+            target_difficulty,
+            extend_floor: args.extend_floor,
+            extend_limit: args.extend_limit,
+        }
+    }
+
+    /// A policy that simply runs for a fixed number of seconds.
+    pub fn timed(seconds: u64) -> Self {
+        StopPolicy {
+            cutoff_time: seconds,
+            min_difficulty: 0,
+            target_difficulty: None,
+            extend_floor: None,
+            extend_limit: None,
+        }
+    }
+
+    /// Raises the floor to keep hashing for past the cutoff, with an optional limit in
+    /// seconds. The higher floor and the shorter limit win, and an early-stop target
+    /// below the floor is raised to it as well.
+    pub fn with_floor(self, floor: u32, limit: Option<u64>) -> Self {
+        let floor = self.extend_floor.map_or(floor, |current| current.max(floor));
+        let extend_limit = match (self.extend_limit, limit) {
+            (Some(current), Some(limit)) => Some(current.min(limit)),
+            (current, limit) => current.or(limit),
+        };
+        StopPolicy {
+            target_difficulty: self.target_difficulty.map(|target| target.max(floor)),
+            extend_floor: Some(floor),
+            extend_limit,
+            ..self
+        }
+    }
+
+    /// Returns true once the best difficulty found is good enough to submit. Stopping
+    /// early only ends hashing; solo miners still wait for the submission window.
+    pub fn is_done(&self, elapsed: u64, best_difficulty: u32) -> bool {
+        // Submit early once the target is reached
+        if let Some(target) = self.target_difficulty {
+            if best_difficulty.ge(&target.max(self.min_difficulty)) {
+                return true;
+            }
+        }
+        if elapsed.lt(&self.cutoff_time) {
+            return false;
+        }
+
+        // Past the cutoff, keep going until the floor (if any) is met
+        let floor = match (self.extend_floor, self.extend_limit) {
+            (Some(_), Some(limit)) if elapsed.ge(&self.cutoff_time.saturating_add(limit)) => {
+                self.min_difficulty
+            }
+            (Some(floor), _) => floor.max(self.min_difficulty),
+            (None, _) => self.min_difficulty,
+        };
+        best_difficulty.ge(&floor)
+    }
+}
+
+/// The lowest difficulty whose base reward is at least `reward` grains.
+fn difficulty_for_reward(config: &Config, reward: u64, min_difficulty: u32) -> u32 {
+    let mut difficulty = min_difficulty;
+    let mut reward_rate = config.base_reward_rate.max(1);
+    while reward_rate.lt(&reward) && difficulty.lt(&64) {
+        reward_rate = reward_rate.saturating_mul(2);
+        difficulty += 1;
+    }
+    difficulty
+}
+
+/// Turns sorted start nonces into contiguous ranges, the last one ending at `end`.
+pub fn nonce_ranges(starts: &[u64], end: u64) -> Vec<Range<u64>> {
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| *start..starts.get(i + 1).copied().unwrap_or(end))
+        .collect()
+}
+
+/// Splits a nonce range into `parts` contiguous ranges, the last one absorbing the remainder.
+pub fn split_range(range: &Range<u64>, parts: u64) -> Vec<Range<u64>> {
+    let parts = parts.max(1);
+    let unit = range.end.saturating_sub(range.start).saturating_div(parts);
+    let starts: Vec<u64> = (0..parts)
+        .map(|n| range.start.saturating_add(unit.saturating_mul(n)))
+        .collect();
+    nonce_ranges(&starts, range.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resplit_adds_threads_by_halving_the_largest_ranges() {
+        assert_eq!(
+            resplit_ranges(vec![0..100, 100..120], 3),
+            vec![0..50, 50..100, 100..120]
+        );
+        assert_eq!(
+            resplit_ranges(vec![0..60, 60..100], 4),
+            vec![0..30, 30..60, 60..80, 80..100]
+        );
+    }
+
+    #[test]
+    fn resplit_retires_threads_by_dropping_the_smallest_ranges() {
+        assert_eq!(
+            resplit_ranges(vec![0..10, 10..100, 100..130], 2),
+            vec![10..100, 100..130]
+        );
+        assert_eq!(resplit_ranges(vec![0..10, 10..100], 0), vec![10..100]);
+    }
+
+    #[test]
+    fn resplit_skips_exhausted_and_unsplittable_ranges() {
+        assert_eq!(resplit_ranges(vec![5..5, 7..8], 3), vec![7..8]);
+        assert!(resplit_ranges(vec![5..5, 9..9], 2).is_empty());
+    }
+
+    #[test]
+    fn floors_keep_the_higher_floor_and_the_shorter_limit() {
+        let policy = StopPolicy {
+            extend_floor: Some(12),
+            extend_limit: Some(5),
+            target_difficulty: Some(10),
+            ..StopPolicy::timed(30)
+        };
+        let raised = policy.with_floor(15, Some(20));
+        assert_eq!(raised.extend_floor, Some(15));
+        assert_eq!(raised.extend_limit, Some(5));
+        assert_eq!(raised.target_difficulty, Some(15));
+
+        let lowered = policy.with_floor(8, Some(2));
+        assert_eq!(lowered.extend_floor, Some(12));
+        assert_eq!(lowered.extend_limit, Some(2));
+
+        let unbounded = StopPolicy::timed(30).with_floor(15, None);
+        assert_eq!(unbounded.extend_limit, None);
+        assert_eq!(unbounded.with_floor(15, Some(10)).extend_limit, Some(10));
+    }
+
+    #[test]
+    fn extension_gives_up_on_the_floor_after_the_limit() {
+        let policy = StopPolicy {
+            min_difficulty: 8,
+            ..StopPolicy::timed(30)
+        }
+        .with_floor(15, Some(10));
+        assert!(!policy.is_done(20, 20));
+        assert!(!policy.is_done(35, 12));
+        assert!(policy.is_done(35, 15));
+        assert!(policy.is_done(40, 8));
+        assert!(!policy.is_done(40, 7));
+    }
+
+    #[test]
+    fn hash_range_stops_once_the_core_count_changes() {
+        let job = HashJob {
+            challenge: [0; 32],
+            nonce_ranges: vec![0..500, 500..1_000],
+            policy: StopPolicy::timed(60),
+        };
+        let control = MiningControl::new();
+        control.start();
+        let mut nonce_range = 0..1_000;
+        let result = hash_range(
+            &job,
+            &mut nonce_range,
+            Instant::now(),
+            control.cores_epoch().wrapping_add(1),
+            &Throttle::default(),
+            &HashProgress::default(),
+            &control,
+        );
+        assert_eq!(result.hashes, 0);
+        assert_eq!(nonce_range, 0..1_000);
+    }
+}
@@ -1,7 +1,10 @@
 use ore_api::consts::TREASURY_ADDRESS;
 use solana_sdk::{signature::Signer, transaction::Transaction};
 
-use crate::Miner;
+use crate::{
+    output::{self, TxOutcome},
+    Miner,
+};
 
 impl Miner {
     pub async fn initialize(&self) {
@@ -20,6 +23,6 @@ impl Miner {
             blockhash,
         );
         let res = self.rpc_client.send_and_confirm_transaction(&tx).await;
-        println!("{:?}", res);
+        output::print(&TxOutcome::new(&res), |_| println!("{:?}", res));
     }
 }
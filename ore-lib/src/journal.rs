@@ -0,0 +1,60 @@
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+use colored::*;
+use serde::Serialize;
+
+use crate::{
+    error::Error,
+    output::{self, status},
+};
+
+/// A record of something the miner did on its own, like an auto-claim.
+#[derive(Clone, Debug, Serialize)]
+pub struct JournalEntry<'a, T: Serialize> {
+    /// A unix timestamp.
+    pub at: i64,
+    pub event: &'a str,
+    #[serde(flatten)]
+    pub data: &'a T,
+}
+
+/// An append-only file of journal entries, one JSON line each.
+#[derive(Clone, Debug)]
+pub struct Journal {
+    pub path: PathBuf,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn append<T: Serialize>(&self, entry: &JournalEntry<T>) -> Result<(), Error> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+}
+
+/// Reports an automatic action as a JSON event and, if there is a journal, appends it there.
+pub fn record<T: Serialize>(journal: Option<&Journal>, event: &str, data: &T) {
+    let entry = JournalEntry {
+        at: chrono::Utc::now().timestamp(),
+        event,
+        data,
+    };
+    output::event(&entry);
+    if let Some(journal) = journal {
+        if let Err(err) = journal.append(&entry) {
+            status(format!(
+                "{} Failed to write to journal {}: {:?}",
+                "WARNING".bold().yellow(),
+                journal.path.display(),
+                err
+            ));
+        }
+    }
+}
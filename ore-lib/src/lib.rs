@@ -1,26 +1,65 @@
 pub mod args;
+pub mod auto_claim;
+pub mod boost_select;
+pub mod bus;
 pub mod commands;
+pub mod confirm;
+pub mod control;
+pub mod core_alloc;
 pub mod cu_limits;
 pub mod dynamic_fee;
 pub mod error;
+pub mod hash_backend;
+pub mod journal;
 pub mod miner;
+pub mod nonce;
 pub mod open;
+pub mod output;
 pub mod pool;
+pub mod profile;
+pub mod profitability;
+pub mod remote;
 pub mod send_and_confirm;
+pub mod throttle;
+pub mod token_account;
 pub mod utils;
 
+pub use commands::{
+    balance::{Balances, PoolBalance},
+    benchmark::BenchmarkResult,
+    boosts::{BoostInfo, BoostList, StakePosition},
+    busses::BusInfo,
+    claim::ClaimOutput,
+    close::CloseOutput,
+    config::ConfigInfo,
+    mine::MineSubmission,
+    proof::ProofInfo,
+    rewards::RewardRate,
+    stake::{PoolShareInfo, StakeInfo, StakeTxOutput},
+    transfer::TransferOutput,
+    transfer_batch::{BatchTransferOutput, PayoutRow, PayoutStatus},
+    unstake::UnstakeOutput,
+    upgrade::UpgradeOutput,
+};
+
+use colored::*;
+use futures::future::{select, Either};
 use lazy_static::lazy_static;
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 use tokio::sync::Mutex;
-use std::sync::atomic::{AtomicBool, Ordering};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use online::check;
 
 use args::MineArgs;
+use control::{CancelMode, MiningControl};
 use error::Error;
 use miner::Miner;
+use output::status;
+use profile::Profile;
+use utils::{stream_jito_tips, JITO_URL};
 
 lazy_static! {
     static ref GLOBAL_MANAGER: Arc<Mutex<Manager>> = Arc::new(Mutex::new(Manager::default()));
@@ -29,8 +68,39 @@ lazy_static! {
 pub struct Manager {
     pub miner: Miner,
     pub mining_args: MineArgs,
-    pub is_mining: Arc<AtomicBool>,
+    pub control: MiningControl,
     pub stop_sender: mpsc::Sender<()>,
+    pub tip_stream: Option<TipStream>,
+}
+
+/// Jito's tip stream, followed on a background thread until dropped.
+pub struct TipStream {
+    /// Dropping this ends the stream.
+    _stop: oneshot::Sender<()>,
+}
+
+impl TipStream {
+    /// Keeps `tip` at the median landed tip. The caller may not be inside a runtime,
+    /// so the stream gets its own.
+    pub fn spawn(tip: Arc<std::sync::RwLock<u64>>) -> Self {
+        let (stop, stopped) = oneshot::channel();
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(err) => {
+                    return status(format!("{} {}", "WARNING".bold().yellow(), err));
+                }
+            };
+            let stream = Box::pin(stream_jito_tips(tip));
+            if let Either::Left((Err(err), _)) = runtime.block_on(select(stream, stopped)) {
+                status(format!("{} {}", "WARNING".bold().yellow(), err));
+            }
+        });
+        TipStream { _stop: stop }
+    }
 }
 
 impl Default for Miner {
@@ -52,27 +122,15 @@ impl Default for Miner {
     }
 }
 
-impl Default for MineArgs {
-    fn default() -> Self {
-        MineArgs {
-            pool_url: None,
-            cores: 1,
-            buffer_time: 0,
-            boost_1: None,
-            boost_2: None,
-            boost_3: None,
-        }
-    }
-}
-
 impl Manager {
     pub fn default() -> Self {
         let (stop_sender, _) = mpsc::channel(1);
         Manager {
             miner: Miner::default(),
             mining_args: MineArgs::default(),
-            is_mining: Arc::new(AtomicBool::new(false)),
+            control: MiningControl::new(),
             stop_sender,
+            tip_stream: None,
         }
     }
 
@@ -83,8 +141,45 @@ impl Manager {
         Manager {
             miner,
             mining_args,
-            is_mining: Arc::new(AtomicBool::new(false)),
+            control: MiningControl::new(),
             stop_sender,
+            tip_stream: None,
+        }
+    }
+
+    /// A manager for `profile`, falling back to the solana cli config for the rpc url
+    /// and keypair. With `jito` set, the tip follows Jito's tip stream for as long as
+    /// the manager lives.
+    pub fn from_profile(profile: &Profile) -> Self {
+        let cli_config = solana_cli_config::CONFIG_FILE
+            .as_ref()
+            .and_then(|config_file| solana_cli_config::Config::load(config_file).ok())
+            .unwrap_or_default();
+        let cluster = profile.rpc.clone().unwrap_or(cli_config.json_rpc_url);
+        let keypair = profile.keypair.clone().unwrap_or(cli_config.keypair_path);
+        let fee_payer = profile.fee_payer.clone().unwrap_or(keypair.clone());
+        let miner = Miner::new(
+            Arc::new(RpcClient::new_with_commitment(
+                cluster,
+                CommitmentConfig::confirmed(),
+            )),
+            profile.priority_fee,
+            Some(keypair),
+            profile.dynamic_fee_url.clone(),
+            profile.dynamic_fee.unwrap_or(false),
+            Some(fee_payer),
+            Arc::new(RpcClient::new(JITO_URL.to_string())),
+            Arc::new(std::sync::RwLock::new(0)),
+        );
+        let mut mining_args = MineArgs::default();
+        profile.merge_mine_args(&mut mining_args, |_| false);
+        let tip_stream = profile
+            .jito
+            .unwrap_or(false)
+            .then(|| TipStream::spawn(Arc::clone(&miner.tip)));
+        Manager {
+            tip_stream,
+            ..Manager::new(miner, mining_args)
         }
     }
 
@@ -101,17 +196,17 @@ impl Manager {
     }
 
     pub fn start_mining(&mut self) -> Result<(), Error> {
-        if self.is_mining.load(Ordering::SeqCst) {
+        if self.control.is_mining() {
             return Err(Error::AlreadyMining);
         }
 
-        self.is_mining.store(true, Ordering::SeqCst);
-        let is_mining = Arc::clone(&self.is_mining);
+        self.control.start();
+        let control = self.control.clone();
         let mining_args = self.mining_args.clone();
         let miner = self.miner.clone();
 
         tokio::spawn(async move {
-            if let Err(e) = miner.mine(mining_args.clone(), &is_mining).await {
+            if let Err(e) = miner.mine(mining_args.clone(), &control).await {
                 eprintln!("Error during mining: {:?}", e);
             }
         });
@@ -119,14 +214,32 @@ impl Manager {
         Ok(())
     }
 
+    /// Changes the number of mining threads, at the next round or, if `immediate`,
+    /// mid-round without dropping the current round.
+    pub fn set_cores(&mut self, cores: u64, immediate: bool) -> Result<(), Error> {
+        if cores.eq(&0) {
+            return Err(Error::Internal("cores must be at least 1".to_string()));
+        }
+
+        self.mining_args.cores = cores;
+        self.control.set_cores(cores, immediate);
+        println!("ore-lib: Cores set to {}", cores);
+
+        Ok(())
+    }
+
     pub fn stop_mining(&self) -> Result<(), Error> {
-        if !self.is_mining.load(Ordering::SeqCst) {
+        self.stop_mining_with(self.mining_args.cancel_mode)
+    }
+
+    pub fn stop_mining_with(&self, mode: CancelMode) -> Result<(), Error> {
+        if !self.control.is_mining() {
             return Err(Error::NotMining);
         }
 
-        self.is_mining.store(false, Ordering::SeqCst);
-        println!("ore-lib: Mining stopped");
-        
+        self.control.stop(mode);
+        println!("ore-lib: Mining stopped ({:?})", mode);
+
         Ok(())
     }
 }
@@ -1,31 +1,48 @@
 mod commands;
+mod confirm;
+mod control;
+mod core_alloc;
 mod args;
+mod auto_claim;
+mod boost_select;
+mod bus;
 mod cu_limits;
 mod dynamic_fee;
 mod error;
+mod hash_backend;
+mod journal;
 #[cfg(feature = "admin")]
 mod initialize;
 mod open;
+mod output;
 mod pool;
+mod profile;
+mod profitability;
+mod remote;
 mod send_and_confirm;
+mod throttle;
+mod token_account;
 mod utils;
 mod miner;
+mod nonce;
 
-use futures::StreamExt;
 use miner::Miner;
+use output::OutputFormat;
 use pool::Pool;
-use std::sync::{atomic::AtomicBool, Arc, RwLock};
-use tokio_tungstenite::connect_async;
-use tokio_tungstenite::tungstenite::protocol::Message;
+use profile::Profile;
+use confirm::AutoYes;
+use control::{CancelMode, MiningControl};
+use std::sync::{Arc, RwLock};
 
 use args::*;
-use clap::{command, Parser, Subcommand};
+use clap::{command, parser::ValueSource, CommandFactory, FromArgMatches, Parser, Subcommand};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     signature::{read_keypair_file, Keypair},
 };
-use utils::Tip;
+use colored::*;
+use utils::{stream_jito_tips, JITO_URL};
 
 #[derive(Subcommand, Debug)]
 enum Commands {
@@ -35,6 +52,9 @@ enum Commands {
     #[command(about = "Benchmark your hashpower")]
     Benchmark(BenchmarkArgs),
 
+    #[command(about = "List boosts and your stakes in them")]
+    Boosts(BoostsArgs),
+
     #[command(about = "Fetch the bus account balances")]
     Busses(BussesArgs),
 
@@ -47,8 +67,11 @@ enum Commands {
     #[command(about = "Fetch the program config")]
     Config(ConfigArgs),
 
+    #[command(about = "Estimate your mining rewards and costs per hour")]
+    Estimate(EstimateArgs),
+
     #[command(about = "Start mining")]
-    Mine(MineArgs),
+    Mine(Box<MineArgs>),
 
     #[command(about = "Fetch a proof account by address")]
     Proof(ProofArgs),
@@ -62,12 +85,18 @@ enum Commands {
     #[command(about = "Send ORE to anyone, anywhere in the world")]
     Transfer(TransferArgs),
 
+    #[command(about = "Unstake tokens from a boost")]
+    Unstake(UnstakeArgs),
+
     #[command(about = "Upgrade your ORE tokens from v1 to v2")]
     Upgrade(UpgradeArgs),
 
     #[command(about = "Update your on-chain pool balance on-demand")]
     UpdatePoolBalance(UpdatePoolBalanceArgs),
 
+    #[command(about = "Hash nonce ranges for a coordinating miner on your network")]
+    Worker(WorkerArgs),
+
     #[cfg(feature = "admin")]
     #[command(about = "Initialize the program")]
     Initialize(InitializeArgs),
@@ -93,6 +122,22 @@ struct Args {
     )]
     config_file: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Named profile to load defaults from. Flags passed explicitly take precedence.",
+        global = true
+    )]
+    profile: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Filepath to the profiles file. Defaults to ~/.config/ore/profiles.yml.",
+        global = true
+    )]
+    profile_file: Option<String>,
+
     #[arg(
         long,
         value_name = "KEYPAIR_FILEPATH",
@@ -129,6 +174,33 @@ struct Args {
     #[arg(long, help = "Enable dynamic priority fees", global = true)]
     dynamic_fee: bool,
 
+    #[arg(
+        long,
+        help = "Disable dynamic priority fees, even if the profile enables them.",
+        overrides_with = "dynamic_fee",
+        global = true
+    )]
+    no_dynamic_fee: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        help = "Output format. With json, each command prints one JSON document to stdout.",
+        default_value_t = OutputFormat::Text,
+        global = true
+    )]
+    output: OutputFormat,
+
+    #[arg(
+        long,
+        visible_alias = "assume-yes",
+        short = 'y',
+        help = "Answer yes to every confirmation prompt.",
+        global = true
+    )]
+    yes: bool,
+
     #[arg(
         long,
         value_name = "JITO",
@@ -137,13 +209,52 @@ struct Args {
     )]
     jito: bool,
 
+    #[arg(
+        long,
+        help = "Don't add a jito tip, even if the profile enables it.",
+        overrides_with = "jito",
+        global = true
+    )]
+    no_jito: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[tokio::main]
 async fn main() {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    output::set_format(args.output);
+
+    // Merge the named profile, if any, under the explicitly passed flags
+    if let Some(name) = &args.profile {
+        let profile = Profile::load(args.profile_file.as_deref(), name).unwrap_or_else(|err| {
+            eprintln!("error: Could not load profile `{}`: {}", name, err);
+            std::process::exit(1);
+        });
+        args.rpc = args.rpc.or(profile.rpc.clone());
+        args.keypair = args.keypair.or(profile.keypair.clone());
+        args.fee_payer = args.fee_payer.or(profile.fee_payer.clone());
+        args.dynamic_fee_url = args.dynamic_fee_url.or(profile.dynamic_fee_url.clone());
+        let is_explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if !is_explicit("dynamic_fee") && !is_explicit("no_dynamic_fee") {
+            args.dynamic_fee = profile.dynamic_fee.unwrap_or(args.dynamic_fee);
+        }
+        if !is_explicit("jito") && !is_explicit("no_jito") {
+            args.jito = profile.jito.unwrap_or(args.jito);
+        }
+        if matches.value_source("priority_fee") != Some(ValueSource::CommandLine) {
+            args.priority_fee = profile.priority_fee.or(args.priority_fee);
+        }
+        if let (Commands::Mine(mine_args), Some(mine_matches)) =
+            (&mut args.command, matches.subcommand_matches("mine"))
+        {
+            profile.merge_mine_args(mine_args, |id| {
+                mine_matches.value_source(id) == Some(ValueSource::CommandLine)
+            });
+        }
+    }
 
     // Load the config file from custom path, the default path, or use default config values
     let cli_config = if let Some(config_file) = &args.config_file {
@@ -162,32 +273,20 @@ async fn main() {
     let default_keypair = args.keypair.unwrap_or(cli_config.keypair_path.clone());
     let fee_payer_filepath = args.fee_payer.unwrap_or(default_keypair.clone());
     let rpc_client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
-    let jito_client =
-        RpcClient::new("https://mainnet.block-engine.jito.wtf/api/v1/transactions".to_string());
+    let jito_client = RpcClient::new(JITO_URL.to_string());
 
     let tip = Arc::new(RwLock::new(0_u64));
     let tip_clone = Arc::clone(&tip);
 
     if args.jito {
-        let url = "ws://bundles-api-rest.jito.wtf/api/v1/bundles/tip_stream";
-        let (ws_stream, _) = connect_async(url).await.unwrap();
-        let (_, mut read) = ws_stream.split();
-
         tokio::spawn(async move {
-            while let Some(message) = read.next().await {
-                if let Ok(Message::Text(text)) = message {
-                    if let Ok(tips) = serde_json::from_str::<Vec<Tip>>(&text) {
-                        for item in tips {
-                            let mut tip = tip_clone.write().unwrap();
-                            *tip = (item.landed_tips_50th_percentile * (10_f64).powf(9.0)) as u64;
-                        }
-                    }
-                }
+            if let Err(err) = stream_jito_tips(tip_clone).await {
+                output::status(format!("{} {}", "WARNING".bold().yellow(), err));
             }
         });
     }
 
-    let miner = Arc::new(Miner::new(
+    let mut miner = Miner::new(
         Arc::new(rpc_client),
         args.priority_fee,
         Some(default_keypair),
@@ -196,7 +295,11 @@ async fn main() {
         Some(fee_payer_filepath),
         Arc::new(jito_client),
         tip,
-    ));
+    );
+    if args.yes {
+        miner = miner.with_confirmer(Arc::new(AutoYes));
+    }
+    let miner = Arc::new(miner);
 
     // Execute user command.
     match args.command {
@@ -206,12 +309,15 @@ async fn main() {
         Commands::Benchmark(args) => {
             miner.benchmark(args).await;
         }
+        Commands::Boosts(args) => {
+            miner.boosts(args).await;
+        }
         Commands::Busses(_) => {
             miner.busses().await;
         }
         Commands::Claim(args) => {
             if let Err(err) = miner.claim(args).await {
-                println!("{:?}", err);
+                output::print_error(err);
             }
         }
         Commands::Close(_) => {
@@ -220,9 +326,30 @@ async fn main() {
         Commands::Config(_) => {
             miner.config().await;
         }
+        Commands::Estimate(args) => {
+            miner.estimate(args).await;
+        }
         Commands::Mine(args) => {
-            if let Err(err) = miner.mine(args, &Arc::new(AtomicBool::new(true))).await {
-                println!("{:?}", err);
+            // Wind down on the first ctrl-c, abort on the second
+            let control = MiningControl::new();
+            control.start();
+            let cancel_mode = args.cancel_mode;
+            let signal_control = control.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    signal_control.stop(cancel_mode);
+                    output::status(format!(
+                        "\nStopping ({:?}). Press ctrl-c again to abort.",
+                        cancel_mode
+                    ));
+                }
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    signal_control.stop(CancelMode::Immediate);
+                    std::process::exit(130);
+                }
+            });
+            if let Err(err) = miner.mine(*args, &control).await {
+                output::print_error(err);
             }
         }
         Commands::Proof(args) => {
@@ -237,6 +364,9 @@ async fn main() {
         Commands::Transfer(args) => {
             miner.transfer(args).await;
         }
+        Commands::Unstake(args) => {
+            miner.unstake(args).await;
+        }
         Commands::Upgrade(args) => {
             miner.upgrade(args).await;
         }
@@ -245,8 +375,20 @@ async fn main() {
                 http_client: reqwest::Client::new(),
                 pool_url: args.pool_url,
             };
-            if let Err(err) = pool.post_update_balance(miner.as_ref()).await {
-                println!("{:?}", err);
+            match pool.post_update_balance(miner.as_ref()).await {
+                Ok(update) => output::print(&update, |update| {
+                    println!(
+                        "Updated on-chain pool balance: {} ORE ({})",
+                        utils::amount_u64_to_string(update.balance),
+                        update.tx.signature.clone().unwrap_or_default()
+                    )
+                }),
+                Err(err) => output::print_error(err),
+            }
+        }
+        Commands::Worker(args) => {
+            if let Err(err) = miner.worker(args).await {
+                output::print_error(err);
             }
         }
         #[cfg(feature = "admin")]
@@ -254,4 +396,7 @@ async fn main() {
             miner.initialize().await;
         }
     }
+    if output::failed() {
+        std::process::exit(1);
+    }
 }
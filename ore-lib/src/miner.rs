@@ -2,6 +2,11 @@ use solana_client::nonblocking::rpc_client::RpcClient;
 use std::sync::Arc;
 use solana_sdk::signature::{read_keypair_file, Keypair};
 
+use crate::{
+    confirm::{Confirmer, TerminalConfirmer},
+    hash_backend::{CpuBackend, HashBackend},
+};
+
 #[derive(Clone)]
 pub struct Miner {
     pub keypair_filepath: Option<String>,
@@ -12,6 +17,8 @@ pub struct Miner {
     pub fee_payer_filepath: Option<String>,
     pub jito_client: Arc<RpcClient>,
     pub tip: Arc<std::sync::RwLock<u64>>,
+    pub hash_backend: Option<Arc<dyn HashBackend>>,
+    pub confirmer: Arc<dyn Confirmer>,
 }
 
 impl Miner {
@@ -34,9 +41,29 @@ impl Miner {
             fee_payer_filepath,
             jito_client,
             tip,
+            hash_backend: None,
+            confirmer: Arc::new(TerminalConfirmer),
         }
     }
 
+    /// The backend used to search for hashes, defaulting to the given CPU backend.
+    pub fn backend(&self, cpu: CpuBackend) -> Arc<dyn HashBackend> {
+        self.hash_backend.clone().unwrap_or_else(|| Arc::new(cpu))
+    }
+
+    /// Replaces the default CPU backend used to search for hashes.
+    #[allow(dead_code)] // Only library users plug in their own backend
+    pub fn with_hash_backend(mut self, hash_backend: Arc<dyn HashBackend>) -> Self {
+        self.hash_backend = Some(hash_backend);
+        self
+    }
+
+    /// Replaces the terminal prompt used to confirm claims, transfers and the like.
+    pub fn with_confirmer(mut self, confirmer: Arc<dyn Confirmer>) -> Self {
+        self.confirmer = confirmer;
+        self
+    }
+
     pub fn signer(&self) -> Keypair {
         match self.keypair_filepath.clone() {
             Some(filepath) => read_keypair_file(filepath.clone())
@@ -52,4 +79,4 @@ impl Miner {
             None => panic!("No fee payer keypair provided"),
         }
     }
-}
\ No newline at end of file
+}
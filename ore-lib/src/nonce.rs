@@ -0,0 +1,166 @@
+use std::ops::Range;
+
+use rand::Rng;
+
+use crate::hash_backend::split_range;
+
+/// Hands out disjoint nonce ranges: one slice of the nonce space per pool member,
+/// split into one range per thread. Solo miners are a pool of one.
+#[derive(Clone, Debug)]
+pub struct NonceAllocator {
+    index: u64,
+    members: u64,
+    randomize: bool,
+}
+
+impl NonceAllocator {
+    pub fn solo(randomize: bool) -> Self {
+        Self::pool(0, randomize)
+    }
+
+    /// An allocator for the pool member with the given `index`.
+    pub fn pool(index: u64, randomize: bool) -> Self {
+        Self {
+            index,
+            members: index.saturating_add(1),
+            randomize,
+        }
+    }
+
+    /// Updates the number of pool members, returning true if it changed.
+    /// The count never drops below `index + 1`, so this member's slice is never empty.
+    pub fn set_members(&mut self, members: u64) -> bool {
+        let members = members.max(self.index.saturating_add(1));
+        let changed = members.ne(&self.members);
+        self.members = members;
+        changed
+    }
+
+    pub fn members(&self) -> u64 {
+        self.members
+    }
+
+    /// This member's slice of the nonce space.
+    pub fn member_range(&self) -> Range<u64> {
+        member_range(self.index, self.members)
+    }
+
+    /// One disjoint range per thread within this member's slice. If randomized, each
+    /// range starts at a random point in its first half, so a restart within the same
+    /// round does not repeat the nonces hashed before it.
+    pub fn thread_ranges(&self, threads: u64) -> Vec<Range<u64>> {
+        let mut ranges = split_range(&self.member_range(), threads);
+        if self.randomize {
+            let mut rng = rand::thread_rng();
+            for range in ranges.iter_mut() {
+                let half = range.end.saturating_sub(range.start) / 2;
+                range.start += rng.gen_range(0..=half);
+            }
+        }
+        ranges
+    }
+
+    /// Like `thread_ranges`, but always randomized, for hashing the same challenge again
+    /// without repeating the nonces hashed before.
+    pub fn retry_ranges(&self, threads: u64) -> Vec<Range<u64>> {
+        Self {
+            randomize: true,
+            ..self.clone()
+        }
+        .thread_ranges(threads)
+    }
+}
+
+/// The slice of the nonce space for member `index` out of `members`, the last member
+/// taking the remainder.
+pub fn member_range(index: u64, members: u64) -> Range<u64> {
+    let members = members.max(index.saturating_add(1));
+    let unit = u64::MAX.saturating_div(members);
+    let start = unit.saturating_mul(index);
+    let end = if index.eq(&(members - 1)) {
+        u64::MAX
+    } else {
+        start.saturating_add(unit)
+    };
+    start..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_disjoint(mut ranges: Vec<Range<u64>>) {
+        ranges.sort_by_key(|range| range.start);
+        for range in ranges.iter() {
+            assert!(range.start.lt(&range.end), "empty range {:?}", range);
+        }
+        for pair in ranges.windows(2) {
+            assert!(pair[0].end.le(&pair[1].start), "{:?} overlaps {:?}", pair[0], pair[1]);
+        }
+    }
+
+    fn all_ranges(members: u64, threads: u64, randomize: bool) -> Vec<Range<u64>> {
+        (0..members)
+            .flat_map(|index| {
+                let mut allocator = NonceAllocator::pool(index, randomize);
+                allocator.set_members(members);
+                allocator.thread_ranges(threads)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ranges_are_disjoint_for_small_counts() {
+        for members in 1..=24 {
+            for threads in 1..=24 {
+                assert_disjoint(all_ranges(members, threads, false));
+                assert_disjoint(all_ranges(members, threads, true));
+            }
+        }
+    }
+
+    #[test]
+    fn ranges_are_disjoint_for_arbitrary_counts() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let members = rng.gen_range(1..=2_000);
+            let threads = rng.gen_range(1..=256);
+            assert_disjoint(all_ranges(members, threads, rng.gen()));
+        }
+    }
+
+    #[test]
+    fn ranges_cover_the_nonce_space() {
+        let mut ranges = all_ranges(7, 3, false);
+        ranges.sort_by_key(|range| range.start);
+        assert_eq!(ranges.first().map(|range| range.start), Some(0));
+        assert_eq!(ranges.last().map(|range| range.end), Some(u64::MAX));
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+    }
+
+    #[test]
+    fn membership_changes_keep_the_member_slice() {
+        let mut allocator = NonceAllocator::pool(5, false);
+        assert!(!allocator.set_members(3));
+        assert_eq!(allocator.members(), 6);
+        assert!(!allocator.member_range().is_empty());
+        assert!(allocator.set_members(10));
+        assert_eq!(allocator.member_range(), member_range(5, 10));
+        assert_disjoint(allocator.thread_ranges(16));
+    }
+
+    #[test]
+    fn retries_start_somewhere_new_within_each_thread_range() {
+        let allocator = NonceAllocator::solo(false);
+        let ranges = allocator.thread_ranges(4);
+        let retries = allocator.retry_ranges(4);
+        assert_disjoint(retries.clone());
+        assert_ne!(ranges, retries);
+        for (range, retry) in ranges.iter().zip(retries.iter()) {
+            assert!(retry.start.ge(&range.start));
+            assert_eq!(retry.end, range.end);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use solana_sdk::signature::Signer;
 
-use crate::{send_and_confirm::ComputeBudget, utils::proof_pubkey, miner::Miner};
+use crate::{miner::Miner, output::status, send_and_confirm::ComputeBudget, utils::proof_pubkey};
 
 impl Miner {
     pub async fn open(&self) {
@@ -13,7 +13,7 @@ impl Miner {
         }
 
         // Sign and send transaction.
-        println!("Generating challenge...");
+        status("Generating challenge...");
         let ix = ore_api::sdk::open(signer.pubkey(), signer.pubkey(), fee_payer.pubkey());
         self.send_and_confirm(&[ix], ComputeBudget::Fixed(400_000), false)
             .await
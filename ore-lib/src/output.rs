@@ -0,0 +1,149 @@
+use std::{
+    fmt::{Debug, Display},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use clap::ValueEnum;
+use colored::*;
+use serde::Serialize;
+use solana_client::client_error::Result as ClientResult;
+use solana_sdk::signature::Signature;
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+/// How commands print their results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+
+    /// A single JSON document on stdout, with progress messages moved to stderr.
+    Json,
+}
+
+pub fn set_format(format: OutputFormat) {
+    JSON.store(format.eq(&OutputFormat::Json), Ordering::Relaxed);
+}
+
+pub fn format() -> OutputFormat {
+    if JSON.load(Ordering::Relaxed) {
+        OutputFormat::Json
+    } else {
+        OutputFormat::Text
+    }
+}
+
+pub fn is_json() -> bool {
+    format().eq(&OutputFormat::Json)
+}
+
+/// Prints a progress message, to stderr in JSON mode so stdout stays parseable.
+pub fn status(msg: impl Display) {
+    if is_json() {
+        eprintln!("{}", msg);
+    } else {
+        println!("{}", msg);
+    }
+}
+
+/// Prints `value` as JSON, or with `text` in text mode.
+pub fn print<T: Serialize>(value: &T, text: impl FnOnce(&T)) {
+    if is_json() {
+        println!("{}", serde_json::to_string_pretty(value).unwrap());
+    } else {
+        text(value);
+    }
+}
+
+/// Prints `value` as one compact JSON line, in JSON mode only. Long-running commands
+/// report what they do as they go with these, rather than with one final document.
+pub fn event<T: Serialize>(value: &T) {
+    if is_json() {
+        println!("{}", serde_json::to_string(value).unwrap());
+    }
+}
+
+/// Whether a command has printed an error.
+pub fn failed() -> bool {
+    FAILED.load(Ordering::Relaxed)
+}
+
+/// Prints a failed command's error.
+pub fn print_error(err: impl Debug) {
+    FAILED.store(true, Ordering::Relaxed);
+    print(
+        &ErrorOutput {
+            error: format!("{:?}", err),
+        },
+        |output| println!("{} {}", "ERROR".bold().red(), output.error),
+    );
+}
+
+#[derive(Serialize)]
+struct ErrorOutput {
+    error: String,
+}
+
+/// What happened to a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxStatus {
+    Confirmed,
+    /// Sent without waiting for confirmation.
+    Sent,
+    Failed,
+    /// Declined at the confirmation prompt.
+    Cancelled,
+}
+
+/// The outcome of a transaction, as reported by commands that send one.
+#[derive(Clone, Debug, Serialize)]
+pub struct TxOutcome {
+    pub signature: Option<String>,
+    pub status: TxStatus,
+    pub error: Option<String>,
+}
+
+impl TxOutcome {
+    pub fn new(result: &ClientResult<Signature>) -> Self {
+        match result {
+            Ok(sig) => Self::confirmed(sig),
+            Err(err) => Self::failed(err),
+        }
+    }
+
+    pub fn confirmed(sig: &Signature) -> Self {
+        Self {
+            signature: Some(sig.to_string()),
+            status: TxStatus::Confirmed,
+            error: None,
+        }
+    }
+
+    pub fn sent(sig: &Signature) -> Self {
+        Self {
+            signature: Some(sig.to_string()),
+            status: TxStatus::Sent,
+            error: None,
+        }
+    }
+
+    pub fn failed(err: impl Display) -> Self {
+        Self {
+            signature: None,
+            status: TxStatus::Failed,
+            error: Some(err.to_string()),
+        }
+    }
+
+    pub fn cancelled() -> Self {
+        Self {
+            signature: None,
+            status: TxStatus::Cancelled,
+            error: None,
+        }
+    }
+}
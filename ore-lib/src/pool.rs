@@ -8,13 +8,23 @@ use ore_pool_types::{
     BalanceUpdate, ContributePayload, Member, MemberChallenge, PoolAddress, RegisterPayload,
     RegisterStakerPayload, Staker, UpdateBalancePayload,
 };
+use serde::Serialize;
 use solana_rpc_client::spinner;
 use solana_sdk::{
     compute_budget, pubkey::Pubkey, signature::Signature, signer::Signer, transaction::Transaction,
 };
 use steel::AccountDeserialize;
 
-use crate::{cu_limits::CU_LIMIT_CLAIM, error::Error, send_and_confirm::ComputeBudget, miner::Miner, utils::test_internet_connection};
+use crate::{cu_limits::CU_LIMIT_CLAIM, error::Error, send_and_confirm::ComputeBudget, miner::Miner, output::{status, TxOutcome}, utils::test_internet_connection};
+
+/// The outcome of attributing a member's pool balance on-chain.
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateBalanceOutput {
+    /// The balance attributed, in grains.
+    pub balance: u64,
+    #[serde(flatten)]
+    pub tx: TxOutcome,
+}
 
 pub struct Pool {
     pub http_client: reqwest::Client,
@@ -23,7 +33,7 @@ pub struct Pool {
 
 impl Pool {
     pub async fn post_pool_register(&self, miner: &Miner) -> Result<Member, Error> {
-        println!("post_pool_register");
+        status("post_pool_register");
         let pubkey = miner.signer().pubkey();
         let post_url = format!("{}/register", self.pool_url);
         // check if on-chain member account exists already
@@ -42,7 +52,7 @@ impl Pool {
         let resp = self.http_client.post(post_url).json(&body).send().await?;
         match resp.error_for_status() {
             Err(err) => {
-                println!("{:?}", err);
+                status(format!("{:?}", err));
                 Err(err).map_err(From::from)
             }
             Ok(resp) => resp.json::<Member>().await.map_err(From::from),
@@ -62,7 +72,7 @@ impl Pool {
             .get_staker_onchain(miner, pool_address.address, *mint)
             .await
         {
-            println!("creating new share account");
+            status("creating new share account");
             // on-chain staker account not found
             // create one before submitting register payload to pool
             let ix = ore_pool_api::sdk::open_share(pubkey, *mint, pool_address.address);
@@ -81,7 +91,7 @@ impl Pool {
         let resp = self.http_client.post(post_url).json(&body).send().await?;
         match resp.error_for_status() {
             Err(err) => {
-                println!("{:?}", err);
+                status(format!("{:?}", err));
                 Err(err).map_err(From::from)
             }
             Ok(resp) => resp.json::<Staker>().await.map_err(From::from),
@@ -89,56 +99,56 @@ impl Pool {
     }
 
     pub async fn get_pool_address(&self) -> Result<PoolAddress, Error> {
-        println!("Entering get_pool_address");
-        println!("Online? {}", check(Some(3)).await.is_ok());
+        status("Entering get_pool_address");
+        status(format!("Online? {}", check(Some(3)).await.is_ok()));
         let get_url = format!("{}/pool-address", self.pool_url);
-        println!("get_url: {}", get_url);
+        status(format!("get_url: {}", get_url));
 
         let start_time = Instant::now();
         let timeout_duration = Duration::from_secs(60);
         let retry_delay = Duration::from_secs(5);
 
         while start_time.elapsed() < timeout_duration {
-            println!("Testing internet connection...");
+            status("Testing internet connection...");
             match test_internet_connection().await {
-                true => println!("Internet connection test passed"),
+                true => status("Internet connection test passed"),
                 false => {
-                    println!("No internet connection. Retrying in {} seconds", retry_delay.as_secs());
+                    status(format!("No internet connection. Retrying in {} seconds", retry_delay.as_secs()));
                     tokio::time::sleep(retry_delay).await;
                     continue;
                 }
             }
 
-            println!("Sending GET request");
+            status("Sending GET request");
             match self.http_client.get(&get_url).timeout(Duration::from_secs(10)).send().await {
                 Ok(response) => {
-                    println!("Request successful, response: {:?}", response);
+                    status(format!("Request successful, response: {:?}", response));
                     return match response.error_for_status() {
                         Ok(resp) => {
-                            println!("Parsing response as JSON");
+                            status("Parsing response as JSON");
                             let result = resp.json::<PoolAddress>().await;
-                            println!("JSON parsing result: {:?}", result);
+                            status(format!("JSON parsing result: {:?}", result));
                             result.map_err(|e| Error::Internal(format!("Failed to parse JSON: {}", e)))
                         }
                         Err(err) => {
-                            println!("Error in response status: {:?}", err);
+                            status(format!("Error in response status: {:?}", err));
                             Err(Error::Internal(format!("HTTP error: {}", err)))
                         }
                     };
                 }
                 Err(e) => {
-                    println!("Error sending request: {:?}", e);
+                    status(format!("Error sending request: {:?}", e));
                     if e.is_timeout() {
-                        println!("Request timed out, retrying...");
+                        status("Request timed out, retrying...");
                     } else if e.is_connect() {
-                        println!("Connection error, retrying...");
+                        status("Connection error, retrying...");
                     } else {
                         return Err(Error::Internal(format!("Failed to send request: {}", e)));
                     }
                 }
             }
 
-            println!("Retrying in {} seconds", retry_delay.as_secs());
+            status(format!("Retrying in {} seconds", retry_delay.as_secs()));
             tokio::time::sleep(retry_delay).await;
         }
 
@@ -176,7 +186,7 @@ impl Pool {
         let resp = self.http_client.get(get_url).send().await?;
         match resp.error_for_status() {
             Err(err) => {
-                println!("{:?}", err);
+                status(format!("{:?}", err));
                 Err(err).map_err(From::from)
             }
             Ok(resp) => resp.json::<Member>().await.map_err(From::from),
@@ -206,7 +216,7 @@ impl Pool {
         }
     }
 
-    pub async fn post_update_balance(&self, miner: &Miner) -> Result<(), Error> {
+    pub async fn post_update_balance(&self, miner: &Miner) -> Result<UpdateBalanceOutput, Error> {
         let signer = &miner.signer();
         let signer_pubkey = &signer.pubkey();
         let post_url = format!("{}/update-balance", self.pool_url);
@@ -248,13 +258,15 @@ impl Pool {
             .await?;
         match resp.error_for_status() {
             Err(err) => {
-                println!("{:?}", err);
+                status(format!("{:?}", err));
                 Err(err).map_err(From::from)
             }
             Ok(resp) => {
-                let balance_update = resp.json::<BalanceUpdate>().await;
-                println!("{:?}", balance_update);
-                Ok(())
+                let balance_update = resp.json::<BalanceUpdate>().await?;
+                Ok(UpdateBalanceOutput {
+                    balance: balance_update.balance,
+                    tx: TxOutcome::confirmed(&balance_update.signature),
+                })
             }
         }
     }
@@ -264,7 +276,7 @@ impl Pool {
         let resp = self.http_client.get(get_url).send().await?;
         match resp.error_for_status() {
             Err(err) => {
-                println!("{:?}", err);
+                status(format!("{:?}", err));
                 Err(err).map_err(From::from)
             }
             Ok(resp) => resp.json::<MemberChallenge>().await.map_err(From::from),
@@ -292,7 +304,7 @@ impl Pool {
             .await?;
         match resp.error_for_status() {
             Err(err) => {
-                println!("{:?}", err);
+                status(format!("{:?}", err));
                 Err(err).map_err(From::from)
             }
             Ok(_) => Ok(()),
@@ -0,0 +1,202 @@
+use std::{collections::HashMap, path::Path};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::{args::MineArgs, boost_select::parse_boosts, error::Error};
+
+lazy_static! {
+    /// The default path to the ore profiles file, `~/.config/ore/profiles.yml`.
+    pub static ref PROFILES_FILE: Option<String> =
+        solana_cli_config::CONFIG_FILE.as_ref().and_then(|config_file| {
+            Path::new(config_file)
+                .ancestors()
+                .nth(3)
+                .map(|dir| dir.join("ore").join("profiles.yml"))
+                .and_then(|path| path.to_str().map(|path| path.to_string()))
+        });
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Profiles {
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// A named set of defaults for the global and mining flags.
+/// Flags passed on the command line always take precedence.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Profile {
+    pub rpc: Option<String>,
+    pub keypair: Option<String>,
+    pub fee_payer: Option<String>,
+    pub priority_fee: Option<u64>,
+    pub dynamic_fee_url: Option<String>,
+    pub dynamic_fee: Option<bool>,
+    pub jito: Option<bool>,
+    pub pool_url: Option<String>,
+    pub cores: Option<u64>,
+    pub buffer_time: Option<u64>,
+    pub boosts: Vec<String>,
+    pub max_duty_cycle: Option<u8>,
+    pub batch_sleep_ms: Option<u64>,
+    pub max_temp: Option<f64>,
+}
+
+impl Profile {
+    /// Loads a profile by name from the given file, or from `PROFILES_FILE` if none is given.
+    pub fn load(profiles_file: Option<&str>, name: &str) -> Result<Self, Error> {
+        let Some(profiles_file) = profiles_file.or(PROFILES_FILE.as_deref()) else {
+            return Err(Error::Internal(
+                "could not locate the profiles file".to_string(),
+            ));
+        };
+        let mut profiles: Profiles = solana_cli_config::load_config_file(profiles_file)?;
+        let profile = profiles
+            .profiles
+            .remove(name)
+            .ok_or_else(|| Error::ProfileNotFound(name.to_string()))?;
+
+        // Check the boost mints
+        parse_boosts(profile.boosts.iter().map(String::as_str))?;
+        Ok(profile)
+    }
+
+    /// Fills in the mining args the user did not set explicitly.
+    pub fn merge_mine_args(&self, args: &mut MineArgs, is_explicit: impl Fn(&str) -> bool) {
+        if args.pool_url.is_none() {
+            args.pool_url = self.pool_url.clone();
+        }
+        if let Some(cores) = self.cores {
+            if !is_explicit("cores") {
+                args.cores = cores;
+            }
+        }
+        if let Some(buffer_time) = self.buffer_time {
+            if !is_explicit("buffer_time") {
+                args.buffer_time = buffer_time;
+            }
+        }
+        if args.max_duty_cycle.is_none() {
+            args.max_duty_cycle = self.max_duty_cycle;
+        }
+        if let Some(batch_sleep_ms) = self.batch_sleep_ms {
+            if !is_explicit("batch_sleep_ms") {
+                args.batch_sleep_ms = batch_sleep_ms;
+            }
+        }
+        if args.max_temp.is_none() {
+            args.max_temp = self.max_temp;
+        }
+        if args.boosts.is_empty() {
+            args.boosts = parse_boosts(self.boosts.iter().map(String::as_str)).unwrap_or_default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+
+    const MINT: &str = "oreoU2P8bN6jkk3jbaiVxYnG1dCXcYxwhwyK9jSybcp";
+
+    fn write_profiles(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "ore-profiles-{}-{}.yml",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn load_reads_the_named_profile() {
+        let path = write_profiles(
+            "load",
+            &format!(
+                "profiles:\n  rig:\n    rpc: http://localhost:8899\n    cores: 8\n    jito: true\n    boosts: [{}]\n  other:\n    cores: 2\n",
+                MINT
+            ),
+        );
+        let profile = Profile::load(Some(&path), "rig").unwrap();
+        assert_eq!(profile.rpc.as_deref(), Some("http://localhost:8899"));
+        assert_eq!(profile.cores, Some(8));
+        assert_eq!(profile.jito, Some(true));
+        assert_eq!(profile.dynamic_fee, None);
+        assert_eq!(profile.boosts, vec![MINT.to_string()]);
+
+        assert!(matches!(
+            Profile::load(Some(&path), "missing"),
+            Err(Error::ProfileNotFound(name)) if name == "missing"
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_invalid_boosts() {
+        let path = write_profiles("boosts", "profiles:\n  rig:\n    boosts: [not-a-mint]\n");
+        assert!(matches!(
+            Profile::load(Some(&path), "rig"),
+            Err(Error::InvalidBoost(mint, _)) if mint == "not-a-mint"
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn merge_keeps_explicit_args() {
+        let profile = Profile {
+            pool_url: Some("https://pool".to_string()),
+            cores: Some(8),
+            buffer_time: Some(10),
+            batch_sleep_ms: Some(50),
+            max_duty_cycle: Some(80),
+            max_temp: Some(85.0),
+            boosts: vec![MINT.to_string()],
+            ..Default::default()
+        };
+        let mut args = MineArgs {
+            cores: 2,
+            max_temp: Some(70.0),
+            ..Default::default()
+        };
+        profile.merge_mine_args(&mut args, |id| id == "cores");
+        assert_eq!(args.cores, 2);
+        assert_eq!(args.buffer_time, 10);
+        assert_eq!(args.batch_sleep_ms, 50);
+        assert_eq!(args.max_duty_cycle, Some(80));
+        assert_eq!(args.max_temp, Some(70.0));
+        assert_eq!(args.pool_url.as_deref(), Some("https://pool"));
+        assert_eq!(args.boosts, vec![Pubkey::from_str(MINT).unwrap()]);
+    }
+
+    #[test]
+    fn merge_keeps_boosts_from_the_command_line() {
+        let profile = Profile {
+            boosts: vec![MINT.to_string()],
+            ..Default::default()
+        };
+        let boost = Pubkey::new_unique();
+        let mut args = MineArgs {
+            boosts: vec![boost],
+            ..Default::default()
+        };
+        profile.merge_mine_args(&mut args, |_| false);
+        assert_eq!(args.boosts, vec![boost]);
+    }
+
+    #[test]
+    fn empty_profiles_keep_the_defaults() {
+        let defaults = MineArgs::default();
+        let mut args = MineArgs::default();
+        Profile::default().merge_mine_args(&mut args, |_| false);
+        assert_eq!(args.cores, defaults.cores);
+        assert_eq!(args.buffer_time, defaults.buffer_time);
+        assert!(args.boosts.is_empty());
+    }
+}
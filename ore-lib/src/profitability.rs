@@ -0,0 +1,373 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use ore_api::{consts::ONE_MINUTE, state::Config};
+use ore_boost_api::state::{boost_pda, stake_pda, Boost, Stake};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{native_token::lamports_to_sol, signer::Signer};
+use steel::AccountDeserialize;
+
+use crate::{
+    utils::{amount_u64_to_f64, reward_for_difficulty},
+    Miner,
+};
+
+/// The base fee charged per transaction signature.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// What to do when a solution would cost more SOL to submit than its reward is worth.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum UnprofitablePolicy {
+    /// Submit it anyway.
+    Submit,
+
+    /// Keep hashing past the cutoff for a profitable hash, up to the delay limit, then submit.
+    #[default]
+    Delay,
+
+    /// Keep hashing the same challenge, in rounds of up to the delay limit, until a
+    /// profitable hash is found, never submitting at a loss.
+    Skip,
+}
+
+/// A staked boost, applied the way the ORE program does:
+/// `reward * (multiplier - 1) * balance / total_stake` on top of the base reward.
+#[derive(Clone, Copy, Debug)]
+pub struct BoostShare {
+    pub multiplier: u64,
+    pub balance: u64,
+    pub total_stake: u64,
+}
+
+impl BoostShare {
+    pub fn new(boost: &Boost, stake: &Stake) -> Self {
+        Self {
+            multiplier: boost.multiplier,
+            balance: stake.balance,
+            total_stake: boost.total_stake,
+        }
+    }
+
+    /// The extra reward this boost adds to `reward`.
+    pub fn bonus(&self, reward: u64) -> u64 {
+        if self.total_stake.eq(&0) {
+            return 0;
+        }
+        (reward as u128)
+            .saturating_mul(self.multiplier.saturating_sub(1) as u128)
+            .saturating_mul(self.balance as u128)
+            .checked_div(self.total_stake as u128)
+            .unwrap_or(0)
+            .min(u64::MAX as u128) as u64
+    }
+}
+
+/// The reward with all boosts applied.
+pub fn boosted_reward(reward: u64, boosts: &[BoostShare]) -> u64 {
+    boosts
+        .iter()
+        .fold(reward, |total, boost| total.saturating_add(boost.bonus(reward)))
+}
+
+/// The estimated cost of landing a transaction.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TxCost {
+    pub compute_units: u32,
+    /// In micro-lamports per compute unit.
+    pub priority_fee: u64,
+    pub jito_tip: u64,
+    pub signatures: u64,
+}
+
+impl TxCost {
+    pub fn lamports(&self) -> u64 {
+        let priority_fee = (self.priority_fee as u128)
+            .saturating_mul(self.compute_units as u128)
+            .div_ceil(1_000_000) as u64;
+        self.signatures
+            .saturating_mul(LAMPORTS_PER_SIGNATURE)
+            .saturating_add(priority_fee)
+            .saturating_add(self.jito_tip)
+    }
+}
+
+/// The expected reward of a solution weighed against the cost of submitting it.
+#[derive(Clone, Copy, Debug)]
+pub struct Profitability {
+    pub reward: u64,
+    pub cost: u64,
+    pub ore_price_sol: f64,
+}
+
+impl Profitability {
+    pub fn new(
+        config: &Config,
+        difficulty: u32,
+        boosts: &[BoostShare],
+        cost: &TxCost,
+        ore_price_sol: f64,
+    ) -> Self {
+        Self {
+            reward: boosted_reward(reward_for_difficulty(config, difficulty), boosts),
+            cost: cost.lamports(),
+            ore_price_sol,
+        }
+    }
+
+    pub fn reward_sol(&self) -> f64 {
+        amount_u64_to_f64(self.reward) * self.ore_price_sol
+    }
+
+    pub fn cost_sol(&self) -> f64 {
+        lamports_to_sol(self.cost)
+    }
+
+    pub fn is_profitable(&self) -> bool {
+        self.reward_sol().ge(&self.cost_sol())
+    }
+
+    /// The lowest difficulty whose reward covers the cost, if any.
+    pub fn break_even_difficulty(
+        config: &Config,
+        boosts: &[BoostShare],
+        cost: &TxCost,
+        ore_price_sol: f64,
+    ) -> Option<u32> {
+        (config.min_difficulty as u32..64).find(|difficulty| {
+            Self::new(config, *difficulty, boosts, cost, ore_price_sol).is_profitable()
+        })
+    }
+}
+
+/// Expected mining output for a given hashrate, one submission per round.
+#[derive(Clone, Debug, Serialize)]
+pub struct Estimate {
+    pub hashrate: f64,
+    pub round_time: u64,
+    pub min_difficulty: u32,
+    /// The chance that the best hash of a round has exactly this difficulty.
+    pub distribution: Vec<(u32, f64)>,
+    /// The chance that no hash in a round reaches the min difficulty.
+    pub below_min: f64,
+    /// In grains, with boosts applied.
+    pub reward_per_round: f64,
+    pub ore_per_hour: f64,
+    pub cost_sol_per_hour: f64,
+    pub ore_price_sol: Option<f64>,
+}
+
+impl Estimate {
+    /// With `N` hashes per round, the chance the best hash reaches difficulty `d` is
+    /// `1 - (1 - 2^-d)^N`, since each hash has `d` or more leading zeros with chance `2^-d`.
+    pub fn new(
+        config: &Config,
+        hashrate: f64,
+        round_time: u64,
+        boosts: &[BoostShare],
+        cost: &TxCost,
+        ore_price_sol: Option<f64>,
+    ) -> Self {
+        let hashes = hashrate * round_time as f64;
+        let at_least = |difficulty: u32| -> f64 {
+            let p = 0.5f64.powi(difficulty as i32);
+            -(hashes * (-p).ln_1p()).exp_m1()
+        };
+        let min_difficulty = config.min_difficulty as u32;
+        let distribution: Vec<(u32, f64)> = (0..64)
+            .map(|difficulty| (difficulty, at_least(difficulty) - at_least(difficulty + 1)))
+            .collect();
+        let reward_per_round = distribution
+            .iter()
+            .filter(|(difficulty, _)| difficulty.ge(&min_difficulty))
+            .map(|(difficulty, p)| {
+                let reward = reward_for_difficulty(config, *difficulty);
+                p * boosted_reward(reward, boosts) as f64
+            })
+            .sum::<f64>();
+        let rounds_per_hour = 3600.0 / ONE_MINUTE as f64;
+        Self {
+            hashrate,
+            round_time,
+            min_difficulty,
+            distribution,
+            below_min: 1.0 - at_least(min_difficulty),
+            reward_per_round,
+            ore_per_hour: amount_u64_to_f64(1) * reward_per_round * rounds_per_hour,
+            cost_sol_per_hour: lamports_to_sol(cost.lamports()) * rounds_per_hour,
+            ore_price_sol,
+        }
+    }
+
+    /// The expected profit per hour in SOL, if an ORE price was given.
+    pub fn net_sol_per_hour(&self) -> Option<f64> {
+        self.ore_price_sol
+            .map(|price| self.ore_per_hour * price - self.cost_sol_per_hour)
+    }
+}
+
+impl Miner {
+    /// Estimates what sending a transaction with the given compute budget costs right now.
+    pub async fn estimate_tx_cost(&self, compute_units: u32) -> TxCost {
+        let priority_fee = if self.dynamic_fee {
+            self.dynamic_fee()
+                .await
+                .unwrap_or(self.priority_fee.unwrap_or(0))
+        } else {
+            self.priority_fee.unwrap_or(0)
+        };
+        let signatures = if self.fee_payer().pubkey().eq(&self.signer().pubkey()) {
+            1
+        } else {
+            2
+        };
+        TxCost {
+            compute_units,
+            priority_fee,
+            jito_tip: *self.tip.read().unwrap(),
+            signatures,
+        }
+    }
+
+    /// The signer's share of the boost for `mint`, if it has staked there.
+    pub async fn get_boost_share(&self, mint: Pubkey) -> Option<BoostShare> {
+        let boost_address = boost_pda(mint).0;
+        let stake_address = stake_pda(self.signer().pubkey(), boost_address).0;
+        let accounts = self
+            .rpc_client
+            .get_multiple_accounts(&[boost_address, stake_address])
+            .await
+            .ok()?;
+        let boost = Boost::try_from_bytes(&accounts.first()?.as_ref()?.data).ok()?;
+        let stake = Stake::try_from_bytes(&accounts.get(1)?.as_ref()?.data).ok()?;
+        Some(BoostShare::new(boost, stake))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            base_reward_rate: 1_000,
+            last_reset_at: 0,
+            min_difficulty: 8,
+            top_balance: 0,
+        }
+    }
+
+    #[test]
+    fn boosts_add_the_staked_share_of_the_multiplier() {
+        let boost = BoostShare {
+            multiplier: 3,
+            balance: 25,
+            total_stake: 100,
+        };
+        assert_eq!(boost.bonus(1_000), 500);
+        assert_eq!(boosted_reward(1_000, &[boost, boost]), 2_000);
+        assert_eq!(boosted_reward(1_000, &[]), 1_000);
+
+        let empty = BoostShare {
+            total_stake: 0,
+            ..boost
+        };
+        assert_eq!(empty.bonus(1_000), 0);
+    }
+
+    #[test]
+    fn tx_cost_sums_signatures_priority_fee_and_tip() {
+        let cost = TxCost {
+            compute_units: 500_000,
+            priority_fee: 1_001,
+            jito_tip: 10_000,
+            signatures: 2,
+        };
+        // 500_000 CU at 1_001 micro-lamports rounds up to 501 lamports
+        assert_eq!(cost.lamports(), 2 * LAMPORTS_PER_SIGNATURE + 501 + 10_000);
+        assert_eq!(TxCost::default().lamports(), 0);
+    }
+
+    #[test]
+    fn break_even_is_the_first_difficulty_covering_the_cost() {
+        let config = config();
+        // 5_000 lamports at 1 SOL per ORE takes 500_000 grains, or 2^9 times the base reward
+        let cost = TxCost {
+            signatures: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            Profitability::break_even_difficulty(&config, &[], &cost, 1.0),
+            Some(17)
+        );
+        assert!(!Profitability::new(&config, 16, &[], &cost, 1.0).is_profitable());
+        assert!(Profitability::new(&config, 17, &[], &cost, 1.0).is_profitable());
+
+        // Doubling the reward with a boost breaks even one difficulty sooner
+        let boost = BoostShare {
+            multiplier: 2,
+            balance: 1,
+            total_stake: 1,
+        };
+        assert_eq!(
+            Profitability::break_even_difficulty(&config, &[boost], &cost, 1.0),
+            Some(16)
+        );
+        assert_eq!(
+            Profitability::break_even_difficulty(&config, &[], &cost, 0.0),
+            None
+        );
+    }
+
+    #[test]
+    fn estimate_distribution_sums_to_one() {
+        let config = config();
+        let estimate = Estimate::new(&config, 1_000.0, 60, &[], &TxCost::default(), None);
+        let total: f64 = estimate.distribution.iter().map(|(_, p)| p).sum();
+        assert!((total - 1.0).abs().lt(&1e-9));
+
+        // 60_000 hashes per round almost surely reach the min difficulty of 8
+        assert!(estimate.below_min.lt(&1e-9));
+        let below: f64 = estimate
+            .distribution
+            .iter()
+            .filter(|(difficulty, _)| difficulty.lt(&8))
+            .map(|(_, p)| p)
+            .sum();
+        assert!((below - estimate.below_min).abs().lt(&1e-9));
+        assert_eq!(estimate.net_sol_per_hour(), None);
+    }
+
+    #[test]
+    fn estimate_reward_matches_the_expected_best_hash() {
+        // Small enough that no reward up to difficulty 63 saturates
+        let config = Config {
+            base_reward_rate: 1,
+            ..config()
+        };
+        // With a single hash per round, the best difficulty is d with chance 2^-(d+1)
+        let estimate = Estimate::new(&config, 1.0, 1, &[], &TxCost::default(), Some(1.0));
+        let (difficulty, p) = estimate.distribution[3];
+        assert_eq!(difficulty, 3);
+        assert!((p - 1.0 / 16.0).abs().lt(&1e-12));
+
+        // Each difficulty from 8 doubles the reward and halves the chance, so each
+        // contributes 2^-9 times the base reward
+        let expected = 56.0 / 512.0;
+        assert!((estimate.reward_per_round - expected).abs().lt(&1e-6));
+        assert!(estimate.net_sol_per_hour().unwrap().gt(&0.0));
+    }
+
+    #[test]
+    fn estimate_nets_out_the_hourly_cost() {
+        let config = config();
+        let cost = TxCost {
+            signatures: 1,
+            ..Default::default()
+        };
+        let estimate = Estimate::new(&config, 0.0, 60, &[], &cost, Some(1.0));
+        assert_eq!(estimate.reward_per_round, 0.0);
+        assert!((estimate.cost_sol_per_hour - 0.000005 * 60.0).abs().lt(&1e-12));
+        assert!((estimate.net_sol_per_hour().unwrap() + estimate.cost_sol_per_hour)
+            .abs()
+            .lt(&1e-12));
+    }
+}
@@ -0,0 +1,495 @@
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    ops::Range,
+    sync::{atomic::AtomicBool, atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
+
+use colored::*;
+use ore_api::consts::TOLERANCE;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    control::{CancelMode, MiningControl},
+    error::Error,
+    hash_backend::{split_range, HashBackend, HashJob, HashProgress, HashResult},
+    output::status,
+};
+
+/// How long a coordinator waits to connect to a worker.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How often sockets are polled while a job is running.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How often a worker reports its progress to the coordinator.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The most a coordinator waits for a worker's result after asking it to stop.
+const RESULT_GRACE: Duration = Duration::from_secs(10);
+
+/// The least a coordinator waits for a worker's result, even once the round is due.
+const MIN_RESULT_GRACE: Duration = Duration::from_secs(1);
+
+/// Messages exchanged between a coordinator and its workers, one JSON object per line.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerMessage {
+    /// Coordinator to worker: hash these nonce ranges.
+    Job(HashJob),
+
+    /// Coordinator to worker: stop hashing and send back the best result.
+    Stop,
+
+    /// Worker to coordinator: the best difficulty and hash count so far.
+    Progress { best_difficulty: u32, hashes: u64 },
+
+    /// Worker to coordinator: the best solution found for the job.
+    Result(HashResult),
+}
+
+fn send(stream: &mut TcpStream, message: &WorkerMessage) -> Result<(), Error> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Reads the next message, returning `Ok(None)` if the read timed out before a full line arrived.
+/// Partial lines are kept in `buf` across timeouts.
+fn recv(
+    reader: &mut BufReader<TcpStream>,
+    buf: &mut String,
+) -> Result<Option<WorkerMessage>, Error> {
+    match reader.read_line(buf) {
+        Ok(0) => Err(Error::Internal("connection closed".to_string())),
+        Ok(_) => {
+            let message = serde_json::from_str(buf.trim_end());
+            buf.clear();
+            Ok(Some(message?))
+        }
+        Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Splits each job between the local backend and a set of `ore worker` processes.
+/// The nonce space is divided into one contiguous slice per member, the same way
+/// a pool divides it among `num_total_members`, with the local backend as member 0.
+pub struct ClusterBackend {
+    local: Arc<dyn HashBackend>,
+    workers: Vec<String>,
+}
+
+impl ClusterBackend {
+    pub fn new(local: Arc<dyn HashBackend>, workers: Vec<String>) -> Self {
+        Self { local, workers }
+    }
+}
+
+impl HashBackend for ClusterBackend {
+    fn prepare(&self, challenge: &[u8; 32]) -> Result<(), Error> {
+        self.local.prepare(challenge)
+    }
+
+    fn run(&self, job: &HashJob, progress: &HashProgress, control: &MiningControl) -> HashResult {
+        // Submissions are penalized once the tolerance past the cutoff runs out
+        let deadline = Instant::now()
+            + Duration::from_secs(job.policy.cutoff_time.saturating_add(TOLERANCE as u64));
+
+        // Split the job's nonce space among all members
+        let span = job.nonce_ranges.iter().map(|r| r.start).min().unwrap_or(0)
+            ..job
+                .nonce_ranges
+                .iter()
+                .map(|r| r.end)
+                .max()
+                .unwrap_or(u64::MAX);
+        let members = split_range(&span, self.workers.len() as u64 + 1);
+        let local_job = HashJob {
+            nonce_ranges: split_range(&members[0], job.nonce_ranges.len() as u64),
+            ..job.clone()
+        };
+
+        // Dispatch the remote slices
+        let streams: Vec<TcpStream> = self
+            .workers
+            .iter()
+            .zip(members[1..].iter())
+            .filter_map(|(addr, range)| match dispatch(addr, job, range.clone()) {
+                Ok(stream) => Some(stream),
+                Err(err) => {
+                    status(format!(
+                        "{} Worker {} unavailable: {:?}",
+                        "WARNING".bold().yellow(),
+                        addr,
+                        err
+                    ));
+                    None
+                }
+            })
+            .collect();
+
+        let stopped = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = streams
+                .iter()
+                .filter_map(|stream| stream.try_clone().ok())
+                .map(|stream| {
+                    let stopped = &stopped;
+                    scope.spawn(move || collect(stream, progress, stopped, deadline))
+                })
+                .collect();
+
+            // Hash the local slice, then ask the workers for their best
+            let result = self.local.run(&local_job, progress, control);
+            stopped.store(true, Ordering::SeqCst);
+            for mut stream in streams.iter().filter_map(|s| s.try_clone().ok()) {
+                let _ = send(&mut stream, &WorkerMessage::Stop);
+            }
+
+            // Keep the best solution across all members
+            handles
+                .into_iter()
+                .filter_map(|h| h.join().ok().flatten())
+                .fold(result, HashResult::merge)
+        })
+    }
+}
+
+/// Connects to a worker and sends it a job covering `range`.
+fn dispatch(addr: &str, job: &HashJob, range: Range<u64>) -> Result<TcpStream, Error> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| Error::Internal(format!("could not resolve {}", addr)))?;
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let job = HashJob {
+        nonce_ranges: vec![range],
+        ..job.clone()
+    };
+    send(&mut stream, &WorkerMessage::Job(job))?;
+    Ok(stream)
+}
+
+/// How long to wait for a worker's result once stopped with `time_left` before `deadline`.
+fn result_grace(time_left: Duration) -> Duration {
+    time_left.clamp(MIN_RESULT_GRACE, RESULT_GRACE)
+}
+
+/// Relays a worker's progress until it returns a result, giving up shortly after a stop.
+fn collect(
+    stream: TcpStream,
+    progress: &HashProgress,
+    stopped: &AtomicBool,
+    deadline: Instant,
+) -> Option<HashResult> {
+    let mut reader = BufReader::new(stream);
+    let mut buf = String::new();
+    let mut seen_hashes = 0u64;
+    let mut stopped_at: Option<Instant> = None;
+    loop {
+        match recv(&mut reader, &mut buf) {
+            Ok(Some(WorkerMessage::Progress {
+                best_difficulty,
+                hashes,
+            })) => {
+                progress.record_difficulty(best_difficulty);
+                progress.add_hashes(hashes.saturating_sub(seen_hashes));
+                seen_hashes = seen_hashes.max(hashes);
+            }
+            Ok(Some(WorkerMessage::Result(result))) => {
+                progress.record_difficulty(result.difficulty);
+                progress.add_hashes(result.hashes.saturating_sub(seen_hashes));
+                return Some(result);
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                if stopped.load(Ordering::SeqCst) {
+                    let stopped_at = *stopped_at.get_or_insert_with(Instant::now);
+                    let grace = result_grace(deadline.saturating_duration_since(stopped_at));
+                    if stopped_at.elapsed().ge(&grace) {
+                        return None;
+                    }
+                }
+            }
+            Err(_) => return None,
+        }
+    }
+}
+
+/// Accepts coordinators on `bind` and hashes the jobs they send with `backend`,
+/// splitting each received range across `cores` threads. Blocks forever.
+/// Coordinators aren't authenticated, so `bind` should only be reachable from
+/// trusted machines.
+pub fn serve(bind: &str, cores: u64, backend: Arc<dyn HashBackend>) -> Result<(), Error> {
+    let listener = TcpListener::bind(bind)?;
+    status(format!("Worker listening on {}", listener.local_addr()?));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                status(format!("{} {}", "WARNING".bold().yellow(), err));
+                continue;
+            }
+        };
+        let peer = stream
+            .peer_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_default();
+        if let Err(err) = serve_connection(stream, &peer, cores, backend.as_ref()) {
+            status(format!("Coordinator {} disconnected: {}", peer, err));
+        }
+    }
+    Ok(())
+}
+
+fn serve_connection(
+    stream: TcpStream,
+    peer: &str,
+    cores: u64,
+    backend: &dyn HashBackend,
+) -> Result<(), Error> {
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut buf = String::new();
+    loop {
+        // Wait for the next job
+        let job = match recv(&mut reader, &mut buf) {
+            Ok(Some(WorkerMessage::Job(job))) => job,
+            Ok(_) => continue,
+            Err(_) => return Ok(()),
+        };
+        let job = HashJob {
+            nonce_ranges: job
+                .nonce_ranges
+                .iter()
+                .flat_map(|range| split_range(range, cores))
+                .collect(),
+            ..job
+        };
+        backend.prepare(&job.challenge)?;
+
+        // Hash until done or told to stop, reporting progress as we go
+        let control = MiningControl::new();
+        control.start();
+        let progress = HashProgress::default();
+        let mut disconnected = None;
+        let result = std::thread::scope(|scope| {
+            let handle = scope.spawn(|| backend.run(&job, &progress, &control));
+            let mut last_report = Instant::now();
+            while !handle.is_finished() {
+                match recv(&mut reader, &mut buf) {
+                    Ok(Some(WorkerMessage::Stop)) => control.stop(CancelMode::SubmitBestNow),
+                    Ok(_) => {}
+                    Err(err) => {
+                        control.stop(CancelMode::Immediate);
+                        disconnected = Some(err);
+                    }
+                }
+                if last_report.elapsed().ge(&PROGRESS_INTERVAL) {
+                    last_report = Instant::now();
+                    let message = WorkerMessage::Progress {
+                        best_difficulty: progress.best_difficulty(),
+                        hashes: progress.hashes(),
+                    };
+                    let _ = send(&mut writer, &message);
+                }
+            }
+            handle.join().unwrap_or_default()
+        });
+        if let Some(err) = disconnected {
+            return Err(err);
+        }
+
+        // Return the best solution
+        status(format!(
+            "Job from {}: difficulty {}, {} hashes",
+            peer, result.difficulty, result.hashes
+        ));
+        send(&mut writer, &WorkerMessage::Result(result))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::hash_backend::StopPolicy;
+
+    /// Records the ranges it's asked to hash and finds a fixed difficulty.
+    struct FakeBackend {
+        difficulty: u32,
+        ranges: Mutex<Vec<Range<u64>>>,
+    }
+
+    impl FakeBackend {
+        fn new(difficulty: u32) -> Self {
+            Self {
+                difficulty,
+                ranges: Mutex::new(vec![]),
+            }
+        }
+    }
+
+    impl HashBackend for FakeBackend {
+        fn run(&self, job: &HashJob, _: &HashProgress, _: &MiningControl) -> HashResult {
+            self.ranges
+                .lock()
+                .unwrap()
+                .extend(job.nonce_ranges.iter().cloned());
+            HashResult {
+                difficulty: self.difficulty,
+                hashes: 10,
+                ..Default::default()
+            }
+        }
+    }
+
+    fn connected_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        server.set_read_timeout(Some(POLL_INTERVAL)).unwrap();
+        (client, server)
+    }
+
+    #[test]
+    fn messages_round_trip_one_per_line() {
+        let (mut client, server) = connected_pair();
+        let mut reader = BufReader::new(server);
+        let mut buf = String::new();
+
+        send(&mut client, &WorkerMessage::Stop).unwrap();
+        send(
+            &mut client,
+            &WorkerMessage::Progress {
+                best_difficulty: 17,
+                hashes: 1_000,
+            },
+        )
+        .unwrap();
+        send(
+            &mut client,
+            &WorkerMessage::Job(HashJob {
+                challenge: [7; 32],
+                nonce_ranges: vec![5..10, 10..20],
+                policy: StopPolicy::timed(30),
+            }),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            recv(&mut reader, &mut buf),
+            Ok(Some(WorkerMessage::Stop))
+        ));
+        assert!(matches!(
+            recv(&mut reader, &mut buf),
+            Ok(Some(WorkerMessage::Progress {
+                best_difficulty: 17,
+                hashes: 1_000
+            }))
+        ));
+        match recv(&mut reader, &mut buf) {
+            Ok(Some(WorkerMessage::Job(job))) => {
+                assert_eq!(job.challenge, [7; 32]);
+                assert_eq!(job.nonce_ranges, vec![5..10, 10..20]);
+                assert_eq!(job.policy.cutoff_time, 30);
+            }
+            other => panic!("expected a job, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn partial_lines_survive_timeouts() {
+        let (mut client, server) = connected_pair();
+        let mut reader = BufReader::new(server);
+        let mut buf = String::new();
+
+        assert!(matches!(recv(&mut reader, &mut buf), Ok(None)));
+        client.write_all(br#"{"type":"#).unwrap();
+        client.flush().unwrap();
+        assert!(matches!(recv(&mut reader, &mut buf), Ok(None)));
+        client.write_all(b"\"stop\"}\n").unwrap();
+        assert!(matches!(
+            recv(&mut reader, &mut buf),
+            Ok(Some(WorkerMessage::Stop))
+        ));
+
+        drop(client);
+        assert!(recv(&mut reader, &mut buf).is_err());
+    }
+
+    #[test]
+    fn malformed_lines_are_errors() {
+        let (mut client, server) = connected_pair();
+        let mut reader = BufReader::new(server);
+        let mut buf = String::new();
+        client.write_all(b"{\"type\":\"bogus\"}\n").unwrap();
+        assert!(recv(&mut reader, &mut buf).is_err());
+    }
+
+    #[test]
+    fn workers_get_their_own_slice_of_the_nonce_space() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let worker = Arc::new(FakeBackend::new(20));
+        let server = std::thread::spawn({
+            let worker = Arc::clone(&worker);
+            move || {
+                let (stream, _) = listener.accept().unwrap();
+                serve_connection(stream, "coordinator", 2, worker.as_ref())
+            }
+        });
+
+        let local = Arc::new(FakeBackend::new(12));
+        let cluster = ClusterBackend::new(local.clone(), vec![addr]);
+        let job = HashJob {
+            challenge: [1; 32],
+            nonce_ranges: vec![0..100, 100..200],
+            policy: StopPolicy::timed(0),
+        };
+        let progress = HashProgress::default();
+        let result = cluster.run(&job, &progress, &MiningControl::new());
+        drop(cluster);
+        server.join().unwrap().unwrap();
+
+        assert_eq!(*local.ranges.lock().unwrap(), vec![0..50, 50..100]);
+        assert_eq!(*worker.ranges.lock().unwrap(), vec![100..150, 150..200]);
+        assert_eq!(result.difficulty, 20);
+        assert_eq!(result.hashes, 20);
+        assert_eq!(progress.best_difficulty(), 20);
+    }
+
+    #[test]
+    fn unreachable_workers_leave_the_local_result() {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let local = Arc::new(FakeBackend::new(12));
+        let cluster = ClusterBackend::new(local.clone(), vec![addr]);
+        let job = HashJob {
+            challenge: [1; 32],
+            nonce_ranges: vec![0..50, 50..100],
+            policy: StopPolicy::timed(0),
+        };
+        let result = cluster.run(&job, &HashProgress::default(), &MiningControl::new());
+        assert_eq!(*local.ranges.lock().unwrap(), vec![0..25, 25..50]);
+        assert_eq!(result.difficulty, 12);
+    }
+
+    #[test]
+    fn result_grace_is_clamped_to_the_time_left() {
+        assert_eq!(result_grace(Duration::from_secs(3)), Duration::from_secs(3));
+        assert_eq!(result_grace(Duration::from_secs(60)), RESULT_GRACE);
+        assert_eq!(result_grace(Duration::ZERO), MIN_RESULT_GRACE);
+    }
+}
@@ -43,6 +43,52 @@ pub enum ComputeBudget {
     Fixed(u32),
 }
 
+/// Why a transaction didn't confirm, as far as whether it may have landed.
+#[derive(Debug)]
+pub enum SendError {
+    /// Failed before the transaction was ever sent.
+    NotSent(ClientError),
+    /// Sent without a confirmation. It may still land.
+    Unconfirmed(ClientError),
+    /// Landed and failed.
+    Failed(ClientError),
+}
+
+impl SendError {
+    fn new(sent: bool, err: ClientError) -> Self {
+        if sent {
+            SendError::Unconfirmed(err)
+        } else {
+            SendError::NotSent(err)
+        }
+    }
+
+    fn failed(err: impl ToString) -> Self {
+        SendError::Failed(ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom(err.to_string()),
+        })
+    }
+}
+
+impl From<SendError> for ClientError {
+    fn from(err: SendError) -> Self {
+        match err {
+            SendError::NotSent(err) | SendError::Unconfirmed(err) | SendError::Failed(err) => err,
+        }
+    }
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::NotSent(err) | SendError::Unconfirmed(err) | SendError::Failed(err) => {
+                err.fmt(f)
+            }
+        }
+    }
+}
+
 impl Miner {
     pub async fn send_and_confirm(
         &self,
@@ -50,6 +96,19 @@ impl Miner {
         compute_budget: ComputeBudget,
         skip_confirm: bool,
     ) -> ClientResult<Signature> {
+        self.try_send_and_confirm(ixs, compute_budget, skip_confirm)
+            .await
+            .map_err(ClientError::from)
+    }
+
+    /// Like `send_and_confirm`, telling apart transactions that were never sent, may
+    /// still land, or landed and failed.
+    pub async fn try_send_and_confirm(
+        &self,
+        ixs: &[Instruction],
+        compute_budget: ComputeBudget,
+        skip_confirm: bool,
+    ) -> Result<Signature, SendError> {
         let progress_bar = spinner::new_progress_bar();
         let signer = self.signer();
         let client = self.rpc_client.clone();
@@ -120,6 +179,7 @@ impl Miner {
 
         // Submit tx
         let mut attempts = 0;
+        let mut sent = false;
         loop {
             progress_bar.set_message(format!("Submitting transaction... (attempt {})", attempts,));
 
@@ -151,7 +211,9 @@ impl Miner {
                 }
 
                 // Resign the tx
-                let (hash, _slot) = get_latest_blockhash_with_retries(&client).await?;
+                let (hash, _slot) = get_latest_blockhash_with_retries(&client)
+                    .await
+                    .map_err(|err| SendError::new(sent, err))?;
                 if signer.pubkey() == fee_payer.pubkey() {
                     tx.sign(&[&signer], hash);
                 } else {
@@ -161,6 +223,7 @@ impl Miner {
 
             // Send transaction
             attempts += 1;
+            sent = true;
             match send_client
                 .send_transaction_with_config(&tx, send_cfg)
                 .await
@@ -194,10 +257,7 @@ impl Miner {
                                                                 },
                                                                 _ => {
                                                                     log_error(&progress_bar, &err.to_string(), true);
-                                                                    return Err(ClientError {
-                                                                        request: None,
-                                                                        kind: ClientErrorKind::Custom(err.to_string()),
-                                                                    });
+                                                                    return Err(SendError::failed(err));
                                                                 }
                                                             }
                                                         },
@@ -205,10 +265,7 @@ impl Miner {
                                                         // Non custom instruction error, return
                                                         _ => {
                                                             log_error(&progress_bar, &err.to_string(), true);
-                                                            return Err(ClientError {
-                                                                request: None,
-                                                                kind: ClientErrorKind::Custom(err.to_string()),
-                                                            });
+                                                            return Err(SendError::failed(err));
                                                         }
                                                     }
                                                 },
@@ -216,10 +273,7 @@ impl Miner {
                                                 // Non instruction error, return
                                                 _ => {
                                                     log_error(&progress_bar, &err.to_string(), true);
-                                                    return Err(ClientError {
-                                                        request: None,
-                                                        kind: ClientErrorKind::Custom(err.to_string()),
-                                                    });
+                                                    return Err(SendError::failed(err));
                                                 }
                                             }
                                         } else if let Some(confirmation) =
@@ -267,10 +321,10 @@ impl Miner {
             tokio::time::sleep(Duration::from_millis(GATEWAY_DELAY)).await;
             if attempts > GATEWAY_RETRIES {
                 log_error(&progress_bar, "Max retries", true);
-                return Err(ClientError {
+                return Err(SendError::Unconfirmed(ClientError {
                     request: None,
                     kind: ClientErrorKind::Custom("Max retries".into()),
-                });
+                }));
             }
         }
     }
@@ -0,0 +1,193 @@
+use std::time::{Duration, Instant};
+
+use colored::*;
+
+use crate::{args::MineArgs, output::status};
+
+/// How often a thread re-reads the thermal sensors.
+const TEMP_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long a thread sleeps at a time while paused.
+const PAUSE_STEP: Duration = Duration::from_millis(200);
+
+/// Limits on how hard the mining threads run.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Throttle {
+    /// The max share of wall time, in percent, a thread spends hashing.
+    pub max_duty_cycle: Option<u8>,
+
+    /// A fixed pause after every batch of nonces.
+    pub batch_sleep: Duration,
+
+    /// Pause hashing while the hottest thermal zone is at or above this, in Celsius.
+    pub max_temp: Option<f64>,
+}
+
+impl Throttle {
+    pub fn new(args: &MineArgs) -> Self {
+        if args.max_temp.is_some() && cpu_temp().is_none() {
+            status(format!(
+                "{} No thermal sensors found, ignoring --max-temp",
+                "WARNING".bold().yellow()
+            ));
+        }
+        Throttle {
+            max_duty_cycle: args.max_duty_cycle.filter(|duty| duty.lt(&100)),
+            batch_sleep: Duration::from_millis(args.batch_sleep_ms),
+            max_temp: args.max_temp,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_duty_cycle.is_some() || !self.batch_sleep.is_zero() || self.max_temp.is_some()
+    }
+}
+
+/// Per-thread throttle state, called between batches of nonces.
+pub struct Throttler<'a> {
+    throttle: &'a Throttle,
+    batch_started_at: Instant,
+    temp_checked_at: Option<Instant>,
+}
+
+impl<'a> Throttler<'a> {
+    pub fn new(throttle: &'a Throttle) -> Self {
+        Self {
+            throttle,
+            batch_started_at: Instant::now(),
+            temp_checked_at: None,
+        }
+    }
+
+    /// Sleeps as the throttle requires, waking early if `should_stop` returns true.
+    pub fn pause(&mut self, should_stop: impl Fn() -> bool) {
+        if !self.throttle.is_enabled() {
+            return;
+        }
+
+        // Rest long enough to keep the duty cycle under the limit
+        let mut rest = self.throttle.batch_sleep;
+        if let Some(duty) = self.throttle.max_duty_cycle {
+            let duty = duty.max(1) as u32;
+            rest += self.batch_started_at.elapsed() * (100 - duty) / duty;
+        }
+        sleep_unless(rest, &should_stop);
+
+        // Wait for the device to cool down
+        if let Some(max_temp) = self.throttle.max_temp {
+            let is_fresh = self
+                .temp_checked_at
+                .is_some_and(|at| at.elapsed().lt(&TEMP_CHECK_INTERVAL));
+            if !is_fresh {
+                while cpu_temp().is_some_and(|temp| temp.ge(&max_temp)) && !should_stop() {
+                    std::thread::sleep(PAUSE_STEP);
+                }
+                self.temp_checked_at = Some(Instant::now());
+            }
+        }
+        self.batch_started_at = Instant::now();
+    }
+}
+
+fn sleep_unless(duration: Duration, should_stop: &impl Fn() -> bool) {
+    let until = Instant::now() + duration;
+    while Instant::now().lt(&until) && !should_stop() {
+        std::thread::sleep(PAUSE_STEP.min(until.saturating_duration_since(Instant::now())));
+    }
+}
+
+/// The hottest reading across `/sys/class/thermal`, in Celsius.
+pub fn cpu_temp() -> Option<f64> {
+    std::fs::read_dir("/sys/class/thermal")
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("temp")).ok())
+        .filter_map(|temp| temp.trim().parse::<f64>().ok())
+        .map(|millidegrees| millidegrees / 1000.0)
+        .reduce(f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duty_cycles_of_100_percent_or_more_are_ignored() {
+        let args = MineArgs {
+            max_duty_cycle: Some(100),
+            ..Default::default()
+        };
+        let throttle = Throttle::new(&args);
+        assert_eq!(throttle.max_duty_cycle, None);
+        assert!(!throttle.is_enabled());
+
+        let args = MineArgs {
+            max_duty_cycle: Some(60),
+            batch_sleep_ms: 5,
+            ..Default::default()
+        };
+        let throttle = Throttle::new(&args);
+        assert_eq!(throttle.max_duty_cycle, Some(60));
+        assert_eq!(throttle.batch_sleep, Duration::from_millis(5));
+        assert!(throttle.is_enabled());
+    }
+
+    #[test]
+    fn disabled_throttle_never_sleeps() {
+        let throttle = Throttle::default();
+        let mut throttler = Throttler::new(&throttle);
+        let started = Instant::now();
+        throttler.pause(|| false);
+        assert!(started.elapsed().lt(&Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn batch_sleep_pauses_between_batches() {
+        let throttle = Throttle {
+            batch_sleep: Duration::from_millis(150),
+            ..Default::default()
+        };
+        let mut throttler = Throttler::new(&throttle);
+        let started = Instant::now();
+        throttler.pause(|| false);
+        assert!(started.elapsed().ge(&Duration::from_millis(150)));
+    }
+
+    #[test]
+    fn duty_cycle_rests_in_proportion_to_the_batch() {
+        let throttle = Throttle {
+            max_duty_cycle: Some(50),
+            ..Default::default()
+        };
+        let mut throttler = Throttler::new(&throttle);
+        std::thread::sleep(Duration::from_millis(100));
+        let started = Instant::now();
+        throttler.pause(|| false);
+        let rest = started.elapsed();
+        assert!(rest.ge(&Duration::from_millis(100)));
+        assert!(rest.lt(&Duration::from_millis(1_000)));
+
+        // The next batch is timed from the end of the pause
+        let started = Instant::now();
+        throttler.pause(|| false);
+        assert!(started.elapsed().lt(&Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn stopping_cuts_the_pause_short() {
+        let throttle = Throttle {
+            batch_sleep: Duration::from_secs(30),
+            ..Default::default()
+        };
+        let mut throttler = Throttler::new(&throttle);
+        let started = Instant::now();
+        throttler.pause(|| true);
+        assert!(started.elapsed().lt(&Duration::from_secs(1)));
+    }
+}
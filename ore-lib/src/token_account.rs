@@ -0,0 +1,316 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_sdk::{account::Account, signature::Signer};
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use spl_token_2022::{
+    extension::{memo_transfer::MemoTransfer, BaseStateWithExtensions, StateWithExtensions},
+    state::{Account as TokenAccount, Mint},
+};
+
+use crate::{cu_limits::CU_LIMIT_CREATE_ATA, error::Error, Miner};
+
+/// A mint and the token program that owns it, either the legacy token program or
+/// Token-2022.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenMint {
+    pub address: Pubkey,
+    pub program: Pubkey,
+    pub decimals: u8,
+}
+
+impl TokenMint {
+    /// Fetches `address`, detecting its token program.
+    pub async fn fetch(rpc_client: &RpcClient, address: Pubkey) -> Result<Self, Error> {
+        let account = rpc_client.get_account(&address).await?;
+        if account.owner.ne(&spl_token::ID) && account.owner.ne(&spl_token_2022::ID) {
+            return Err(Error::Internal(format!(
+                "{} is not a mint of a known token program",
+                address
+            )));
+        }
+        let mint = StateWithExtensions::<Mint>::unpack(&account.data)?;
+        Ok(Self {
+            address,
+            program: account.owner,
+            decimals: mint.base.decimals,
+        })
+    }
+
+    /// The associated token account of `wallet`.
+    pub fn ata(&self, wallet: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(wallet, &self.address, &self.program)
+    }
+
+    /// Points an instruction built for the legacy token program, as the ORE SDKs build
+    /// them, at this mint's program, rederiving the associated token accounts of `owners`.
+    pub fn with_program(&self, mut ix: Instruction, owners: &[Pubkey]) -> Instruction {
+        if self.program.eq(&spl_token::ID) {
+            return ix;
+        }
+        for meta in ix.accounts.iter_mut() {
+            if meta.pubkey.eq(&spl_token::ID) {
+                meta.pubkey = self.program;
+            } else if let Some(owner) = owners
+                .iter()
+                .find(|owner| get_associated_token_address(owner, &self.address).eq(&meta.pubkey))
+            {
+                meta.pubkey = self.ata(owner);
+            }
+        }
+        ix
+    }
+}
+
+/// Whether an associated token account exists yet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AtaState {
+    Exists,
+    Missing,
+}
+
+/// A wallet's associated token account for a mint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ata {
+    pub address: Pubkey,
+    pub state: AtaState,
+    /// Creates the account, if missing. It's idempotent, so it still lands if someone
+    /// else creates the account first.
+    pub create_ix: Option<Instruction>,
+}
+
+impl Ata {
+    /// `wallet`'s account given what was fetched at its address, created at `payer`'s
+    /// expense if missing.
+    pub fn new(
+        mint: &TokenMint,
+        payer: &Pubkey,
+        wallet: &Pubkey,
+        account: Option<&Account>,
+    ) -> Self {
+        let address = mint.ata(wallet);
+        match account {
+            Some(_) => Self {
+                address,
+                state: AtaState::Exists,
+                create_ix: None,
+            },
+            None => Self {
+                address,
+                state: AtaState::Missing,
+                create_ix: Some(create_associated_token_account_idempotent(
+                    payer,
+                    wallet,
+                    &mint.address,
+                    &mint.program,
+                )),
+            },
+        }
+    }
+
+    /// The compute units creating the account takes, if it's missing.
+    pub fn compute_units(&self) -> u32 {
+        match self.state {
+            AtaState::Exists => 0,
+            AtaState::Missing => CU_LIMIT_CREATE_ATA,
+        }
+    }
+}
+
+impl Miner {
+    /// `wallet`'s associated token account for `mint`, detecting its token program.
+    /// Missing accounts come with an instruction creating them at the signer's expense.
+    pub async fn get_ata(&self, mint: Pubkey, wallet: &Pubkey) -> Result<Ata, Error> {
+        let mint = TokenMint::fetch(&self.rpc_client, mint).await?;
+        let mut atas = self.get_atas(&mint, &[*wallet]).await?;
+        Ok(atas.remove(0))
+    }
+
+    /// The associated token accounts of `wallets` for `mint`, in order.
+    pub async fn get_atas(&self, mint: &TokenMint, wallets: &[Pubkey]) -> Result<Vec<Ata>, Error> {
+        let addresses: Vec<Pubkey> = wallets.iter().map(|wallet| mint.ata(wallet)).collect();
+        let accounts = self.get_multiple_accounts_chunked(&addresses).await?;
+        let payer = self.signer().pubkey();
+        Ok(wallets
+            .iter()
+            .zip(accounts)
+            .map(|(wallet, account)| Ata::new(mint, &payer, wallet, account.as_ref()))
+            .collect())
+    }
+}
+
+/// Whether `account` is a token account that only accepts transfers preceded by a memo,
+/// as exchange deposit accounts on Token-2022 may.
+pub fn memo_required(account: &Account) -> bool {
+    if account.owner.ne(&spl_token_2022::ID) {
+        return false;
+    }
+    StateWithExtensions::<TokenAccount>::unpack(&account.data).is_ok_and(|state| {
+        state
+            .get_extension::<MemoTransfer>()
+            .is_ok_and(|memo_transfer| memo_transfer.require_incoming_transfer_memos.into())
+    })
+}
+
+/// Whether `token_account` only accepts transfers preceded by a memo. Missing accounts don't.
+pub async fn requires_memo(rpc_client: &RpcClient, token_account: &Pubkey) -> Result<bool, Error> {
+    let account = rpc_client
+        .get_account_with_commitment(token_account, rpc_client.commitment())
+        .await?
+        .value;
+    Ok(account.as_ref().is_some_and(memo_required))
+}
+
+/// A memo signed by `signer`, to go right before the transfer it describes.
+pub fn memo_ix(memo: &str, signer: &Pubkey) -> Instruction {
+    spl_memo::build_memo(memo.as_bytes(), &[signer])
+}
+
+/// Checks a transfer into `token_account` carries a memo if the account requires one.
+pub async fn check_memo(
+    rpc_client: &RpcClient,
+    token_account: &Pubkey,
+    memo: Option<&str>,
+) -> Result<(), Error> {
+    if memo.is_none() && requires_memo(rpc_client, token_account).await? {
+        return Err(Error::Internal(format!(
+            "{} requires a memo on incoming transfers. Pass --memo.",
+            token_account
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use spl_token_2022::{
+        extension::{ExtensionType, StateWithExtensionsMut},
+        state::AccountState,
+    };
+
+    use super::*;
+
+    fn legacy_mint() -> TokenMint {
+        TokenMint {
+            address: Pubkey::new_unique(),
+            program: spl_token::ID,
+            decimals: 11,
+        }
+    }
+
+    fn token_2022_mint() -> TokenMint {
+        TokenMint {
+            program: spl_token_2022::ID,
+            ..legacy_mint()
+        }
+    }
+
+    fn token_account(mint: &TokenMint, wallet: &Pubkey, require_memos: bool) -> Account {
+        let extensions = [ExtensionType::MemoTransfer];
+        let len = ExtensionType::try_calculate_account_len::<TokenAccount>(&extensions).unwrap();
+        let mut data = vec![0; len];
+        let mut state =
+            StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut data).unwrap();
+        state.base = TokenAccount {
+            mint: mint.address,
+            owner: *wallet,
+            state: AccountState::Initialized,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        let memo_transfer = state.init_extension::<MemoTransfer>(true).unwrap();
+        memo_transfer.require_incoming_transfer_memos = require_memos.into();
+        Account {
+            lamports: 1,
+            data,
+            owner: mint.program,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn missing_account_gets_an_idempotent_create() {
+        let mint = token_2022_mint();
+        let (payer, wallet) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ata = Ata::new(&mint, &payer, &wallet, None);
+        assert_eq!(ata.state, AtaState::Missing);
+        assert_eq!(ata.compute_units(), CU_LIMIT_CREATE_ATA);
+        assert_eq!(
+            ata.create_ix,
+            Some(create_associated_token_account_idempotent(
+                &payer,
+                &wallet,
+                &mint.address,
+                &spl_token_2022::ID
+            ))
+        );
+        let create_ix = ata.create_ix.unwrap();
+        assert_eq!(create_ix.accounts[1].pubkey, ata.address);
+    }
+
+    #[test]
+    fn existing_account_needs_nothing() {
+        let mint = legacy_mint();
+        let wallet = Pubkey::new_unique();
+        let account = Account::default();
+        let ata = Ata::new(&mint, &Pubkey::new_unique(), &wallet, Some(&account));
+        assert_eq!(ata.state, AtaState::Exists);
+        assert_eq!(ata.create_ix, None);
+        assert_eq!(ata.compute_units(), 0);
+        assert_eq!(
+            ata.address,
+            get_associated_token_address(&wallet, &mint.address)
+        );
+    }
+
+    #[test]
+    fn ata_depends_on_token_program() {
+        let legacy = legacy_mint();
+        let token_2022 = TokenMint {
+            program: spl_token_2022::ID,
+            ..legacy
+        };
+        let wallet = Pubkey::new_unique();
+        assert_eq!(
+            legacy.ata(&wallet),
+            get_associated_token_address(&wallet, &legacy.address)
+        );
+        assert_ne!(legacy.ata(&wallet), token_2022.ata(&wallet));
+    }
+
+    #[test]
+    fn with_program_rewrites_token_2022_accounts() {
+        let legacy = legacy_mint();
+        let token_2022 = TokenMint {
+            program: spl_token_2022::ID,
+            ..legacy
+        };
+        let (signer, boost) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = ore_boost_api::sdk::deposit(signer, legacy.address, 1);
+        assert_eq!(legacy.with_program(ix.clone(), &[signer, boost]), ix);
+
+        let rewritten = token_2022.with_program(ix.clone(), &[signer]);
+        let keys: Vec<Pubkey> = rewritten.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert!(keys.contains(&spl_token_2022::ID));
+        assert!(keys.contains(&token_2022.ata(&signer)));
+        assert!(!keys.contains(&spl_token::ID));
+        assert!(!keys.contains(&get_associated_token_address(&signer, &legacy.address)));
+    }
+
+    #[test]
+    fn memo_required_reads_the_memo_transfer_extension() {
+        let mint = token_2022_mint();
+        let wallet = Pubkey::new_unique();
+        assert!(memo_required(&token_account(&mint, &wallet, true)));
+        assert!(!memo_required(&token_account(&mint, &wallet, false)));
+
+        let mut legacy = token_account(&mint, &wallet, true);
+        legacy.owner = spl_token::ID;
+        assert!(!memo_required(&legacy));
+        assert!(!memo_required(&Account::default()));
+    }
+}
@@ -1,6 +1,8 @@
-use std::{io::Read, time::Duration};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use cached::proc_macro::cached;
+use futures::StreamExt;
 use ore_api::{
     consts::{
         CONFIG_ADDRESS, MINT_ADDRESS, PROOF, TOKEN_DECIMALS, TOKEN_DECIMALS_V1, TREASURY_ADDRESS,
@@ -17,10 +19,18 @@ use spl_associated_token_account::get_associated_token_address;
 use steel::AccountDeserialize;
 use tokio::time::sleep;
 use tokio::net::TcpStream;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::error::Error;
+use crate::output::status;
 
 pub const BLOCKHASH_QUERY_RETRIES: usize = 5;
 pub const BLOCKHASH_QUERY_DELAY: u64 = 500;
 
+pub const JITO_URL: &str = "https://mainnet.block-engine.jito.wtf/api/v1/transactions";
+pub const JITO_TIP_STREAM_URL: &str = "ws://bundles-api-rest.jito.wtf/api/v1/bundles/tip_stream";
+
 pub async fn _get_treasury(client: &RpcClient) -> Treasury {
     let data = client
         .get_account_data(&TREASURY_ADDRESS)
@@ -88,6 +98,14 @@ pub async fn get_clock(client: &RpcClient) -> Clock {
     bincode::deserialize::<Clock>(&data).expect("Failed to deserialize clock")
 }
 
+/// The base reward (before boosts) for a hash of the given difficulty, in grains.
+pub fn reward_for_difficulty(config: &Config, difficulty: u32) -> u64 {
+    let exponent = difficulty.saturating_sub(config.min_difficulty as u32);
+    config
+        .base_reward_rate
+        .saturating_mul(2u64.saturating_pow(exponent))
+}
+
 pub fn amount_u64_to_string(amount: u64) -> String {
     amount_u64_to_f64(amount).to_string()
 }
@@ -100,21 +118,43 @@ pub fn amount_f64_to_u64(amount: f64) -> u64 {
     (amount * 10f64.powf(TOKEN_DECIMALS as f64)) as u64
 }
 
-pub fn amount_f64_to_u64_v1(amount: f64) -> u64 {
-    (amount * 10f64.powf(TOKEN_DECIMALS_V1 as f64)) as u64
+/// Parses a decimal ORE amount into grains exactly, or `None` if it isn't a plain
+/// decimal or has more fractional digits than ORE has decimals.
+pub fn amount_str_to_u64(amount: &str) -> Option<u64> {
+    let decimals = TOKEN_DECIMALS as usize;
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let is_digits = |digits: &str| digits.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty())
+        || !is_digits(whole)
+        || !is_digits(fraction)
+        || fraction.len().gt(&decimals)
+    {
+        return None;
+    }
+    let whole = match whole {
+        "" => 0,
+        whole => whole.parse::<u64>().ok()?,
+    };
+    let fraction = format!("{:0<width$}", fraction, width = decimals)
+        .parse::<u64>()
+        .ok()?;
+    whole
+        .checked_mul(10u64.pow(TOKEN_DECIMALS as u32))?
+        .checked_add(fraction)
+}
+
+/// Formats grains as an exact decimal ORE amount.
+pub fn amount_u64_to_decimal_string(amount: u64) -> String {
+    let unit = 10u64.pow(TOKEN_DECIMALS as u32);
+    let fraction = format!("{:0width$}", amount % unit, width = TOKEN_DECIMALS as usize);
+    match fraction.trim_end_matches('0') {
+        "" => (amount / unit).to_string(),
+        fraction => format!("{}.{}", amount / unit, fraction),
+    }
 }
 
-pub fn ask_confirm(question: &str) -> bool {
-    println!("{}", question);
-    loop {
-        let mut input = [0];
-        let _ = std::io::stdin().read(&mut input);
-        match input[0] as char {
-            'y' | 'Y' => return true,
-            'n' | 'N' => return false,
-            _ => println!("y/n only please."),
-        }
-    }
+pub fn amount_f64_to_u64_v1(amount: f64) -> u64 {
+    (amount * 10f64.powf(TOKEN_DECIMALS_V1 as f64)) as u64
 }
 
 pub async fn get_latest_blockhash_with_retries(
@@ -165,15 +205,35 @@ pub struct Tip {
     pub ema_landed_tips_50th_percentile: f64,
 }
 
+/// Follows Jito's tip stream, keeping `tip` at the median landed tip, in lamports.
+/// Returns once the stream closes.
+pub async fn stream_jito_tips(tip: Arc<RwLock<u64>>) -> Result<(), Error> {
+    let (ws_stream, _) = connect_async(JITO_TIP_STREAM_URL)
+        .await
+        .map_err(|err| Error::Internal(format!("Failed to connect to the jito tip stream: {}", err)))?;
+    let (_, mut read) = ws_stream.split();
+    while let Some(message) = read.next().await {
+        if let Ok(Message::Text(text)) = message {
+            if let Ok(tips) = serde_json::from_str::<Vec<Tip>>(&text) {
+                for item in tips {
+                    let mut tip = tip.write().unwrap();
+                    *tip = (item.landed_tips_50th_percentile * (10_f64).powf(9.0)) as u64;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 pub async fn test_internet_connection() -> bool {
-    println!("Starting internet connection test");
+    status("Starting internet connection test");
     match TcpStream::connect("8.8.8.8:53").await {
         Ok(_) => {
-            println!("Internet connection test successful");
+            status("Internet connection test successful");
             true
         }
         Err(e) => {
-            println!("Internet connection test failed: {:?}", e);
+            status(format!("Internet connection test failed: {:?}", e));
             false
         }
     }